@@ -1,28 +1,66 @@
 use std::path::PathBuf;
 
+use crate::{filter::Filter, job::Resolution, search::Search, sort::Sort};
+
 pub enum Action {
     Refresh,
     Quit,
-    CursorUp,
-    CursorDown,
+    CursorUp(usize),
+    CursorDown(usize),
     CursorToFirst,
     CursorToLast,
+    CursorToLine(usize),
     ToggleMark,
+    SetSort(Sort),
+    ToggleHidden,
+    StartFilter,
+    SetFilter(Option<Filter>),
+    EndFilter(bool),
     SwitchSrc,
     DuplicateDir,
     ChangeDir(PathBuf),
     ChangeDirToParent(PathBuf),
+    HistoryBack,
+    HistoryForward,
+    OpenHistory,
+    CloseHistory(Option<PathBuf>),
     Execute(PathBuf),
     Edit(PathBuf),
     StartSearch,
-    EndSearch,
-    Search(String),
+    EndSearch(bool),
+    Search(Search),
+    SearchNext,
+    SearchPrev,
     Copy,
     Move,
+    Trash,
     Delete,
+    EndConfirm(bool),
     StartCreateDir,
     StartRename(String),
+    StartBulkRename,
+    EndRenamePreview(bool),
+    StartBatchRename,
+    EndBatchRename(bool),
     EndInputText(Option<String>),
+    OpenFinder,
+    StartGrep,
+    CloseGrep,
+    EditAt(PathBuf, usize),
+    StartPanelize,
+    Panelize(String, Vec<PathBuf>),
+    CancelPanelize,
+    CloseFinder(Option<PathBuf>),
     OpenBookmarks,
     CloseBookmarks(Option<PathBuf>),
+    Undo,
+    Redo,
+    OpenTrash,
+    CloseTrash,
+    RestoreTrash(PathBuf),
+    EmptyTrash,
+    SelectJob,
+    CancelJob,
+    PauseJob,
+    ResolveConflict(usize, Option<(Resolution, bool)>),
 }
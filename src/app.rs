@@ -1,81 +1,177 @@
 use crossterm::event::{KeyCode, KeyEvent};
 use std::{
+    cmp::min,
     io,
     path::{Path, PathBuf},
+    process::{Command as Process, ExitStatus},
     rc::Rc,
     sync::mpsc::Sender,
 };
 use tui::{
     backend::Backend,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     text::{Span, Spans},
     widgets::Paragraph,
     Frame,
 };
 
 use crate::{
-    action::Action, bookmark::Bookmarks, config::Config, dir::Dir, input::InputBox,
+    action::Action,
+    bookmark::Bookmarks,
+    config::Config,
+    confirm::ConfirmDialog,
+    conflict::ConflictDialog,
+    dir::Dir,
+    filter::FilterLine,
+    finder::Finder,
+    grep::Grep,
+    history::HistoryList,
+    input::InputBox,
+    job::{JobEvent, JobKind, JobManager},
+    journal::{Journal, Operation, Record},
+    keymap::{Command, PendingKeys},
+    rename::{BatchRename, RenameFile, RenamePlan, RenamePreview},
     search::SearchLine,
+    shell::ShellCommand,
+    trash::{self, TrashView},
+    watch::Watcher,
 };
 
+enum ConfirmMode {
+    Delete(ConfirmDialog),
+    EmptyTrash(ConfirmDialog),
+}
+
 enum InputMode {
     CreateDir(InputBox),
     Rename(InputBox),
+    Panelize(InputBox),
 }
 
 pub struct App {
     config: Rc<Config>,
-    tx: Sender<String>,
+    jobs: JobManager,
     dirs: [Dir; 2],
     src_index: usize,
     search_line: Option<SearchLine>,
+    filter_line: Option<FilterLine>,
     input_mode: Option<InputMode>,
     bookmarks: Option<Bookmarks>,
+    history: Option<HistoryList>,
+    finder: Option<Finder>,
+    grep: Option<Grep>,
+    trash: Option<TrashView>,
+    // A panelize command still running, and the pane it was started from.
+    shell: Option<(usize, ShellCommand)>,
+    conflict: Option<ConflictDialog>,
+    journal: Journal,
+    confirm_mode: Option<ConfirmMode>,
+    rename_file: Option<RenameFile>,
+    rename_preview: Option<RenamePreview>,
+    batch_rename: Option<BatchRename>,
+    foreground: Option<Process>,
+    watcher: Option<Watcher>,
+    pending: PendingKeys,
     message: String,
 }
 
 impl App {
-    pub fn new(config: Rc<Config>, tx: Sender<String>, path: &Path) -> io::Result<Self> {
+    pub fn new(config: Rc<Config>, tx: Sender<JobEvent>, path: &Path) -> io::Result<Self> {
         let dirs = [
-            Dir::new(Rc::clone(&config), path)?,
-            Dir::new(Rc::clone(&config), path)?,
+            Dir::new(Rc::clone(&config), path),
+            Dir::new(Rc::clone(&config), path),
         ];
         let src_index = 0usize;
+        let jobs = JobManager::new(tx, config.max_jobs());
         Ok(Self {
             config,
-            tx,
+            jobs,
             dirs,
             src_index,
             search_line: None,
+            filter_line: None,
             input_mode: None,
             bookmarks: None,
+            history: None,
+            finder: None,
+            grep: None,
+            trash: None,
+            shell: None,
+            conflict: None,
+            journal: Journal::default(),
+            confirm_mode: None,
+            rename_file: None,
+            rename_preview: None,
+            batch_rename: None,
+            foreground: None,
+            watcher: Watcher::new().ok(),
+            pending: PendingKeys::default(),
             message: String::from("Welcome."),
         })
     }
 
     pub fn on_event(&mut self, key: &KeyEvent) -> Option<Action> {
-        if let Some(ref mut input_mode) = self.input_mode {
+        if let Some(ref mut conflict) = self.conflict {
+            conflict.on_event(key)
+        } else if let Some(ref mut confirm_mode) = self.confirm_mode {
+            match confirm_mode {
+                ConfirmMode::Delete(dialog) => dialog.on_event(key),
+                ConfirmMode::EmptyTrash(dialog) => dialog.on_event(key),
+            }
+        } else if let Some(ref mut preview) = self.rename_preview {
+            preview.on_event(key)
+        } else if let Some(ref mut batch) = self.batch_rename {
+            batch.on_event(key)
+        } else if let Some(ref mut input_mode) = self.input_mode {
             match input_mode {
                 InputMode::CreateDir(input) => input.on_event(key),
                 InputMode::Rename(input) => input.on_event(key),
+                InputMode::Panelize(input) => input.on_event(key),
             }
         } else if let Some(ref mut search_line) = self.search_line {
             search_line.on_event(key)
+        } else if let Some(ref mut filter_line) = self.filter_line {
+            filter_line.on_event(key)
         } else if let Some(ref mut bookmarks) = self.bookmarks {
             bookmarks.on_event(key)
+        } else if let Some(ref mut history) = self.history {
+            history.on_event(key)
+        } else if let Some(ref mut finder) = self.finder {
+            finder.on_event(key)
+        } else if let Some(ref mut grep) = self.grep {
+            grep.on_event(key)
+        } else if let Some(ref mut trash) = self.trash {
+            trash.on_event(key)
+        } else if self.shell.is_some() && key.code == KeyCode::Esc && self.pending.is_empty() {
+            Some(Action::CancelPanelize)
         } else {
-            let action = self.src_dir().on_event(key);
+            let (command, count) = self.pending.push(self.config.keymap(), key)?;
+            let action = self.src_dir().on_command(command, count);
             if action.is_none() {
-                match key.code {
-                    KeyCode::Char('q') => Some(Action::Quit),
-                    KeyCode::Tab => Some(Action::SwitchSrc),
-                    KeyCode::Char('o') => Some(Action::DuplicateDir),
-                    KeyCode::Char('/') => Some(Action::StartSearch),
-                    KeyCode::Char('c') => Some(Action::Copy),
-                    KeyCode::Char('m') => Some(Action::Move),
-                    KeyCode::Char('d') => Some(Action::Delete),
-                    KeyCode::Char('i') => Some(Action::StartCreateDir),
-                    KeyCode::Char('b') => Some(Action::OpenBookmarks),
+                match command {
+                    Command::Quit => Some(Action::Quit),
+                    Command::SwitchSrc => Some(Action::SwitchSrc),
+                    Command::DuplicateDir => Some(Action::DuplicateDir),
+                    Command::StartSearch => Some(Action::StartSearch),
+                    Command::StartFilter => Some(Action::StartFilter),
+                    Command::Copy => Some(Action::Copy),
+                    Command::Move => Some(Action::Move),
+                    Command::Trash => Some(Action::Trash),
+                    Command::Delete => Some(Action::Delete),
+                    Command::OpenTrash => Some(Action::OpenTrash),
+                    Command::Undo => Some(Action::Undo),
+                    Command::Redo => Some(Action::Redo),
+                    Command::StartCreateDir => Some(Action::StartCreateDir),
+                    Command::BulkRename => Some(Action::StartBulkRename),
+                    Command::BatchRename => Some(Action::StartBatchRename),
+                    Command::OpenBookmarks => Some(Action::OpenBookmarks),
+                    Command::OpenHistory => Some(Action::OpenHistory),
+                    Command::OpenFinder => Some(Action::OpenFinder),
+                    Command::StartGrep => Some(Action::StartGrep),
+                    Command::Panelize => Some(Action::StartPanelize),
+                    Command::SelectJob => Some(Action::SelectJob),
+                    Command::CancelJob => Some(Action::CancelJob),
+                    Command::PauseJob => Some(Action::PauseJob),
                     _ => None,
                 }
             } else {
@@ -91,17 +187,50 @@ impl App {
                 for dir in self.dirs.iter_mut() {
                     dir.refresh();
                 }
+                if let Some(ref mut trash) = self.trash {
+                    trash.refresh();
+                }
             }
             Action::SwitchSrc => self.src_index = 1 - self.src_index,
             Action::DuplicateDir => self.duplicate_dir(),
             Action::ChangeDir(path) => self.change_dir(path.as_path()),
             Action::ChangeDirToParent(path) => self.change_dir_to_parent(path.as_path()),
             Action::StartSearch => self.search_line = Some(SearchLine::new()),
-            Action::EndSearch => self.search_line = None,
-            Action::Search(pattern) => self.src_dir_mut().search(pattern),
+            Action::EndSearch(keep) => {
+                if !*keep {
+                    self.src_dir_mut().clear_search();
+                }
+                self.search_line = None;
+            }
+            Action::Search(search) => self.src_dir_mut().search(search),
+            Action::SearchNext | Action::SearchPrev => {
+                if let Some((current, total)) = self.src_dir().search_status() {
+                    self.message = format!("{}/{}", current, total);
+                }
+            }
+            Action::StartFilter => {
+                self.filter_line = Some(FilterLine::new(self.src_dir().filter()));
+            }
+            Action::EndFilter(keep) => {
+                if !*keep {
+                    self.src_dir_mut().on_dispatch(&Action::SetFilter(None));
+                }
+                self.filter_line = None;
+            }
             Action::Copy => self.copy_marks(),
             Action::Move => self.move_marks(),
-            Action::Delete => self.delete_marks(),
+            Action::Trash => self.trash_marks(),
+            Action::Delete => self.confirm_delete(),
+            Action::EndConfirm(yes) => {
+                if *yes {
+                    match self.confirm_mode {
+                        Some(ConfirmMode::Delete(_)) => self.delete_marks(),
+                        Some(ConfirmMode::EmptyTrash(_)) => self.empty_trash(),
+                        _ => {}
+                    }
+                }
+                self.confirm_mode = None;
+            }
             Action::StartCreateDir => {
                 let mode = InputMode::CreateDir(InputBox::new("Dir: ".to_string()));
                 self.input_mode = Some(mode);
@@ -118,81 +247,415 @@ impl App {
                     match self.input_mode {
                         Some(InputMode::CreateDir(_)) => self.create_dir(value),
                         Some(InputMode::Rename(_)) => self.rename(value),
+                        Some(InputMode::Panelize(_)) => self.panelize_command(value),
                         _ => {}
                     }
                 }
                 self.input_mode = None;
             }
+            Action::StartBulkRename => self.start_bulk_rename(),
+            Action::EndRenamePreview(yes) => {
+                if let Some(preview) = self.rename_preview.take() {
+                    if *yes {
+                        self.rename_all(preview.plan());
+                    }
+                }
+            }
+            Action::StartBatchRename => self.start_batch_rename(),
+            Action::EndBatchRename(yes) => {
+                if let Some(batch) = self.batch_rename.take() {
+                    if *yes {
+                        self.rename_all(batch.plan());
+                    }
+                }
+            }
+            Action::OpenFinder => {
+                let dir = self.src_dir();
+                let finder = Finder::new(
+                    &dir.path(),
+                    dir.show_hidden(),
+                    self.config.respect_gitignore(),
+                );
+                self.finder = Some(finder);
+            }
+            Action::CloseFinder(path) => {
+                if let Some(path) = path {
+                    if let Some(parent) = path.parent() {
+                        self.src_dir_mut().change_dir(parent, Some(path));
+                    }
+                }
+                self.finder = None;
+            }
+            Action::StartGrep => {
+                let dir = self.src_dir();
+                let grep = Grep::new(
+                    &dir.path(),
+                    dir.show_hidden(),
+                    self.config.respect_gitignore(),
+                );
+                self.grep = Some(grep);
+            }
+            Action::CloseGrep => self.grep = None,
+            Action::EditAt(path, line) => {
+                let dir = self.src_dir().path();
+                self.config.edit_at(path, *line, &dir);
+            }
+            Action::StartPanelize => {
+                let mode = InputMode::Panelize(InputBox::new("Panelize: ".to_string()));
+                self.input_mode = Some(mode);
+            }
+            Action::Panelize(title, paths) => {
+                self.src_dir_mut().panelize(title.clone(), paths.clone());
+                self.finder = None;
+                self.grep = None;
+            }
+            Action::CancelPanelize => {
+                self.shell = None;
+                self.message = "Canceled.".to_string();
+            }
             Action::OpenBookmarks => self.open_bookmarks(),
             Action::CloseBookmarks(path) => self.close_bookmarks(path),
+            Action::OpenHistory => {
+                // The directory the pane is in is no place to go.
+                let current = self.src_dir().path();
+                let paths = self
+                    .src_dir()
+                    .recent()
+                    .iter()
+                    .filter(|path| **path != current)
+                    .cloned()
+                    .collect();
+                self.history = Some(HistoryList::new(paths));
+            }
+            Action::CloseHistory(path) => {
+                if let Some(path) = path {
+                    self.src_dir_mut().change_dir(path, None);
+                }
+                self.history = None;
+            }
+            Action::Undo => match self.journal.undo() {
+                Ok(record) => self.jobs.push_replay(JobKind::Undo, record),
+                Err(message) => self.message = message.to_string(),
+            },
+            Action::Redo => match self.journal.redo() {
+                Ok(record) => self.jobs.push_replay(JobKind::Redo, record),
+                Err(message) => self.message = message.to_string(),
+            },
+            Action::OpenTrash => self.trash = Some(TrashView::new()),
+            Action::CloseTrash => self.trash = None,
+            Action::RestoreTrash(info) => self.restore_trash(info),
+            Action::EmptyTrash => self.confirm_empty_trash(),
+            Action::SelectJob => self.jobs.select_next(),
+            Action::CancelJob => self.jobs.cancel_selected(),
+            Action::PauseJob => self.jobs.toggle_pause_selected(),
+            Action::ResolveConflict(id, answer) => {
+                self.jobs.resolve(*id, *answer);
+                self.conflict = None;
+                self.open_conflict();
+            }
             _ => {}
         }
     }
-    pub fn push_message(&mut self, message: String) {
-        self.message = message;
+    pub fn on_job_event(&mut self, event: JobEvent) -> Option<Action> {
+        match event {
+            JobEvent::Progress => None,
+            JobEvent::Conflict => {
+                self.open_conflict();
+                None
+            }
+            JobEvent::Finished(id, message, record) => {
+                self.jobs.finish(id);
+                self.journal.push(record);
+                if let Some(ref conflict) = self.conflict {
+                    if conflict.id() == id {
+                        self.conflict = None;
+                        self.open_conflict();
+                    }
+                }
+                self.message = message;
+                Some(Action::Refresh)
+            }
+            JobEvent::Replayed(id, message, record, applied) => {
+                self.jobs.finish(id);
+                self.journal.replayed(record, applied);
+                self.message = message;
+                Some(Action::Refresh)
+            }
+        }
+    }
+
+    // Called on every loop iteration; returns true when the screen needs to be redrawn.
+    pub fn on_tick(&mut self) -> bool {
+        let mut redraw = false;
+        if let Some(ref mut watcher) = self.watcher {
+            let paths = [self.dirs[0].path(), self.dirs[1].path()];
+            watcher.watch(&paths);
+            for path in watcher.changed() {
+                for dir in self.dirs.iter_mut().filter(|dir| dir.path() == path) {
+                    dir.refresh();
+                }
+            }
+        }
+        for dir in self.dirs.iter_mut() {
+            redraw |= dir.on_tick();
+            if let Some(e) = dir.take_error() {
+                self.message = e;
+            }
+        }
+        if let Some(ref mut finder) = self.finder {
+            redraw |= finder.on_tick();
+        }
+        if let Some(ref mut grep) = self.grep {
+            redraw |= grep.on_tick();
+        }
+        redraw |= self.on_shell_tick();
+        if let Some(ref mut confirm_mode) = self.confirm_mode {
+            redraw |= match confirm_mode {
+                ConfirmMode::Delete(dialog) => dialog.on_tick(),
+                ConfirmMode::EmptyTrash(dialog) => dialog.on_tick(),
+            };
+        }
+        redraw
+    }
+
+    // A program that needs the terminal, run by the main loop.
+    pub fn take_foreground(&mut self) -> Option<Process> {
+        self.foreground.take()
+    }
+
+    pub fn on_foreground_exit(&mut self, status: io::Result<ExitStatus>) {
+        let file = match self.rename_file.take() {
+            Some(file) => file,
+            None => return,
+        };
+        match status {
+            Ok(status) if status.success() => match file.read() {
+                Ok(plan) if plan.changes() == 0 => self.message = "No names changed.".to_string(),
+                Ok(plan) => self.rename_preview = Some(RenamePreview::new(plan)),
+                Err(e) => self.message = format!("Err: {}", e),
+            },
+            Ok(status) => self.message = format!("Editor exited with {}.", status),
+            Err(e) => self.message = format!("Err: {}", e),
+        }
     }
 
     fn duplicate_dir(&mut self) {
         let path = self.dest_dir().path();
-        if let Ok(dir) = Dir::new(Rc::clone(&self.config), path.as_path()) {
-            self.dirs[self.src_index] = dir;
-        }
+        self.src_dir_mut().change_dir(path.as_path(), None);
     }
     fn change_dir(&mut self, path: &Path) {
-        if let Ok(dir) = Dir::new(Rc::clone(&self.config), path) {
-            self.dirs[self.src_index] = dir;
-        }
+        self.src_dir_mut().change_dir(path, None);
     }
     fn change_dir_to_parent(&mut self, path: &Path) {
         if let Some(parent_path) = path.parent() {
-            if let Ok(dir) = Dir::new_with_index(Rc::clone(&self.config), parent_path, path) {
-                self.dirs[self.src_index] = dir;
-            }
+            self.src_dir_mut().change_dir(parent_path, Some(path));
         }
     }
     fn copy_marks(&mut self) {
         let path = self.dest_dir().path();
-        let tx = self.tx.clone();
-        self.src_dir_mut().copy_marks(&tx, path.as_path());
+        let marks = self.src_dir_mut().take_marks();
+        self.jobs.push(JobKind::Copy, marks, Some(path));
     }
     fn move_marks(&mut self) {
         let path = self.dest_dir().path();
-        let tx = self.tx.clone();
-        self.src_dir_mut().move_marks(&tx, path.as_path());
+        let marks = self.src_dir_mut().take_marks();
+        self.jobs.push(JobKind::Move, marks, Some(path));
+    }
+    fn confirm_delete(&mut self) {
+        let marks = self.src_dir().marks();
+        if marks.is_empty() {
+            self.message = "No marked entries.".to_string();
+            return;
+        }
+        let mut lines = vec![format!("Delete {} entries permanently?", marks.len())];
+        lines.extend(marks.iter().take(5).map(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            format!("  {}", name)
+        }));
+        if marks.len() > 5 {
+            lines.push(format!("  ... and {} more", marks.len() - 5));
+        }
+        let dialog = ConfirmDialog::new("Delete".to_string(), lines).with_size(marks);
+        self.confirm_mode = Some(ConfirmMode::Delete(dialog));
+    }
+    fn trash_marks(&mut self) {
+        let marks = self.src_dir_mut().take_marks();
+        self.jobs.push(JobKind::Trash, marks, None);
+    }
+    fn restore_trash(&mut self, info: &Path) {
+        self.message = match trash::restore(info) {
+            Ok(path) => format!("Restored {}.", path.display()),
+            Err(e) => format!("Err: {}", e),
+        };
+        self.on_dispatch(&Action::Refresh);
+    }
+    fn confirm_empty_trash(&mut self) {
+        let (len, paths) = match self.trash {
+            Some(ref trash) if trash.len() > 0 => (trash.len(), trash.paths()),
+            _ => return,
+        };
+        let lines = vec![format!("Delete {} entries in the trash permanently?", len)];
+        let dialog = ConfirmDialog::new("Empty Trash".to_string(), lines).with_size(paths);
+        self.confirm_mode = Some(ConfirmMode::EmptyTrash(dialog));
+    }
+    fn empty_trash(&mut self) {
+        if let Some(ref trash) = self.trash {
+            self.jobs.push(JobKind::Delete, trash.paths(), None);
+        }
     }
     fn delete_marks(&mut self) {
-        let tx = self.tx.clone();
-        self.src_dir_mut().delete_marks(&tx);
+        let marks = self.src_dir_mut().take_marks();
+        self.jobs.push(JobKind::Delete, marks, None);
     }
-    fn create_dir(&mut self, name: &String) {
+    fn create_dir(&mut self, name: &str) {
         if !name.is_empty() {
-            self.src_dir_mut().create_dir(name);
+            match self.src_dir_mut().create_dir(name) {
+                Ok(path) => {
+                    let operations = vec![Operation::CreateDir(path)];
+                    let record = Record::new(format!("Create dir {}", name), operations);
+                    self.journal.push(record);
+                }
+                Err(e) => self.message = format!("Err: {}", e),
+            }
             self.src_dir_mut().refresh();
         }
     }
-    fn rename(&mut self, name: &String) {
+    fn rename(&mut self, name: &str) {
         if !name.is_empty() {
-            self.src_dir_mut().rename(name);
+            match self.src_dir_mut().rename(name) {
+                Ok(Some((from, to))) => {
+                    let operations = vec![Operation::rename(&from, &to)];
+                    let record = Record::new(format!("Rename {}", name), operations);
+                    self.journal.push(record);
+                }
+                Ok(None) => {}
+                Err(e) => self.message = format!("Err: {}", e),
+            }
             self.src_dir_mut().refresh();
         }
     }
+    // Lists the paths printed by a shell command, one per line, relative to
+    // the src directory. The command runs in the background and its output
+    // goes to the pane it was started from.
+    fn panelize_command(&mut self, command: &str) {
+        if command.is_empty() {
+            return;
+        }
+        let dir = self.src_dir().path();
+        match ShellCommand::spawn(command, &dir) {
+            Ok(shell) => {
+                self.message = format!("Running !{}  Esc: cancel", command);
+                self.shell = Some((self.src_index, shell));
+            }
+            Err(e) => self.message = format!("Err: {}", e),
+        }
+    }
+    fn on_shell_tick(&mut self) -> bool {
+        let (index, shell) = match self.shell {
+            Some((index, ref mut shell)) => (index, shell),
+            None => return false,
+        };
+        match shell.on_tick() {
+            Some(Ok(output)) if output.status.success() => {
+                let dir = shell.dir().to_path_buf();
+                let paths = String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .filter(|line| !line.is_empty())
+                    .map(|line| dir.join(line).components().collect())
+                    .collect();
+                let title = format!("!{}", shell.command());
+                self.dirs[index].panelize(title, paths);
+                self.message.clear();
+            }
+            Some(Ok(output)) => {
+                let status = output.status;
+                let error = String::from_utf8_lossy(&output.stderr);
+                self.message = match error.lines().find(|line| !line.trim().is_empty()) {
+                    Some(line) => format!("Command exited with {}: {}", status, line.trim()),
+                    None => format!("Command exited with {}.", status),
+                };
+            }
+            Some(Err(e)) => self.message = format!("Err: {}", e),
+            None => return false,
+        }
+        self.shell = None;
+        true
+    }
+    fn start_bulk_rename(&mut self) {
+        if self.src_dir().is_panel() {
+            self.message = "Can't rename entries in a panel.".to_string();
+            return;
+        }
+        let dir = self.src_dir().path();
+        let names = self.src_dir().rename_names();
+        if names.is_empty() {
+            self.message = "No entries to rename.".to_string();
+            return;
+        }
+        match RenameFile::new(&dir, names) {
+            Ok(file) => {
+                self.foreground = Some(self.config.editor(file.path(), &dir));
+                self.rename_file = Some(file);
+            }
+            Err(e) => self.message = format!("Err: {}", e),
+        }
+    }
+    fn start_batch_rename(&mut self) {
+        if self.src_dir().is_panel() {
+            self.message = "Can't rename entries in a panel.".to_string();
+            return;
+        }
+        let dir = self.src_dir().path();
+        let names = self.src_dir().rename_names();
+        if names.is_empty() {
+            self.message = "No entries to rename.".to_string();
+            return;
+        }
+        self.batch_rename = Some(BatchRename::new(&dir, names));
+    }
+    fn rename_all(&mut self, plan: &RenamePlan) {
+        let (operations, result) = plan.apply();
+        let name = format!("Rename {} entries", plan.changes());
+        self.message = match result {
+            Ok(_) => format!("{}.", name),
+            Err(e) => format!("Err: {}", e),
+        };
+        self.journal.push(Record::new(name, operations));
+        self.src_dir_mut().take_marks();
+        self.on_dispatch(&Action::Refresh);
+    }
+    fn open_conflict(&mut self) {
+        if self.conflict.is_none() {
+            self.conflict = self
+                .jobs
+                .conflict()
+                .map(|(id, src, dest)| ConflictDialog::new(id, src, dest));
+        }
+    }
     fn open_bookmarks(&mut self) {
-        self.bookmarks = Some(Bookmarks::new(Rc::clone(&self.config)));
+        self.bookmarks = Some(Bookmarks::new(&self.config));
     }
     fn close_bookmarks(&mut self, path: &Option<PathBuf>) {
         if let Some(path) = path {
-            if let Ok(dir) = Dir::new(Rc::clone(&self.config), path.as_path()) {
-                self.dirs[self.src_index] = dir;
-            }
+            self.src_dir_mut().change_dir(path.as_path(), None);
         }
         self.bookmarks = None;
     }
 
     pub fn on_draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
-        let main_height = area.height - 1;
+        let jobs_height = if self.jobs.len() > 0 {
+            min(self.jobs.len() as u16 + 2, area.height / 3)
+        } else {
+            0
+        };
+        let main_height = area.height - jobs_height - 1;
         let v_chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Length(main_height), Constraint::Length(1)])
+            .constraints([
+                Constraint::Length(main_height),
+                Constraint::Length(1),
+                Constraint::Length(jobs_height),
+            ])
             .split(area);
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
@@ -203,22 +666,62 @@ impl App {
             let paragraph = Paragraph::new(text);
             f.render_widget(paragraph, v_chunks[1]);
         }
+        if !self.pending.is_empty() {
+            let text = vec![Spans::from(vec![Span::raw(self.pending.to_string())])];
+            let paragraph = Paragraph::new(text).alignment(Alignment::Right);
+            f.render_widget(paragraph, v_chunks[1]);
+        }
         for (i, chunk) in chunks.iter().enumerate() {
             let is_src = i == self.src_index;
             self.dirs[i].on_draw(f, *chunk, is_src);
         }
         if let Some(ref mut line) = self.search_line {
+            let status = self.dirs[self.src_index].search_status();
+            line.on_draw(f, v_chunks[1], status);
+        }
+        if let Some(ref mut line) = self.filter_line {
             line.on_draw(f, v_chunks[1]);
         }
         if let Some(ref mut input_mode) = self.input_mode {
             match input_mode {
                 InputMode::CreateDir(input) => input.on_draw(f, v_chunks[1]),
                 InputMode::Rename(input) => input.on_draw(f, v_chunks[1]),
+                InputMode::Panelize(input) => input.on_draw(f, v_chunks[1]),
             }
         }
         if let Some(ref mut bookmarks) = self.bookmarks {
             bookmarks.on_draw(f, chunks[self.src_index]);
         }
+        if let Some(ref mut history) = self.history {
+            history.on_draw(f, chunks[self.src_index]);
+        }
+        if let Some(ref mut trash) = self.trash {
+            trash.on_draw(f, v_chunks[0]);
+        }
+        if let Some(ref mut finder) = self.finder {
+            finder.on_draw(f, v_chunks[0]);
+        }
+        if let Some(ref mut grep) = self.grep {
+            grep.on_draw(f, v_chunks[0]);
+        }
+        if jobs_height > 0 {
+            self.jobs.on_draw(f, v_chunks[2]);
+        }
+        if let Some(ref mut confirm_mode) = self.confirm_mode {
+            match confirm_mode {
+                ConfirmMode::Delete(dialog) => dialog.on_draw(f, v_chunks[0]),
+                ConfirmMode::EmptyTrash(dialog) => dialog.on_draw(f, v_chunks[0]),
+            }
+        }
+        if let Some(ref mut batch) = self.batch_rename {
+            batch.on_draw(f, v_chunks[0]);
+        }
+        if let Some(ref mut preview) = self.rename_preview {
+            preview.on_draw(f, v_chunks[0]);
+        }
+        if let Some(ref mut conflict) = self.conflict {
+            conflict.on_draw(f, v_chunks[0]);
+        }
     }
 
     fn src_dir(&self) -> &Dir {
@@ -0,0 +1,242 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs::Metadata, time::SystemTime};
+
+use crate::job::format_bytes;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Column {
+    Name,
+    Size,
+    Modified,
+    Created,
+    Accessed,
+    Permissions,
+    Owner,
+    Group,
+    Links,
+    Inode,
+}
+
+pub fn default_columns() -> Vec<Column> {
+    vec![Column::Name, Column::Size, Column::Modified]
+}
+
+pub fn format_time(time: Option<SystemTime>) -> String {
+    match time {
+        Some(time) => {
+            let localtime: DateTime<Local> = time.into();
+            localtime.format("%Y-%m-%d %T").to_string()
+        }
+        None => "-------- --:--:--".to_string(),
+    }
+}
+
+// Directories show `<DIR>`, or the number of items when they were counted.
+fn format_size(meta: &Metadata, items: Option<usize>, exact: bool) -> String {
+    if meta.is_dir() {
+        match items {
+            Some(1) => "1 item".to_string(),
+            Some(items) => format!("{} items", items),
+            None => "<DIR>".to_string(),
+        }
+    } else if exact {
+        meta.len().to_string()
+    } else {
+        format_bytes(meta.len())
+    }
+}
+
+#[cfg(unix)]
+fn format_permissions(meta: &Metadata) -> String {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+    let file_type = meta.file_type();
+    let kind = if file_type.is_dir() {
+        'd'
+    } else if file_type.is_symlink() {
+        'l'
+    } else if file_type.is_fifo() {
+        'p'
+    } else if file_type.is_socket() {
+        's'
+    } else if file_type.is_char_device() {
+        'c'
+    } else if file_type.is_block_device() {
+        'b'
+    } else {
+        '-'
+    };
+    let mode = meta.permissions().mode();
+    let mut s = String::new();
+    s.push(kind);
+    // Owner, group and others; setuid, setgid and sticky show in place of x.
+    for (shift, special, c) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = (mode >> shift) & 0o7;
+        s.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        s.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        s.push(match (bits & 0o1 != 0, mode & special != 0) {
+            (true, true) => c,
+            (false, true) => c.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    s
+}
+
+#[cfg(not(unix))]
+fn format_permissions(meta: &Metadata) -> String {
+    if meta.permissions().readonly() {
+        "r-".to_string()
+    } else {
+        "rw".to_string()
+    }
+}
+
+#[cfg(unix)]
+fn user_name(uid: u32) -> Option<String> {
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    let ret = unsafe {
+        libc::getpwuid_r(
+            uid,
+            &mut passwd,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+    if ret != 0 || result.is_null() {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(passwd.pw_name) };
+    Some(name.to_string_lossy().to_string())
+}
+
+#[cfg(unix)]
+fn group_name(gid: u32) -> Option<String> {
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    let mut group: libc::group = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    let ret = unsafe {
+        libc::getgrgid_r(
+            gid,
+            &mut group,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+    if ret != 0 || result.is_null() {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(group.gr_name) };
+    Some(name.to_string_lossy().to_string())
+}
+
+// Looked-up user and group names; ids without a name are shown as numbers.
+#[derive(Default)]
+pub struct Names {
+    users: HashMap<u32, String>,
+    groups: HashMap<u32, String>,
+}
+
+impl Names {
+    #[cfg(unix)]
+    fn user(&mut self, meta: &Metadata) -> String {
+        use std::os::unix::fs::MetadataExt;
+        let uid = meta.uid();
+        self.users
+            .entry(uid)
+            .or_insert_with(|| user_name(uid).unwrap_or_else(|| uid.to_string()))
+            .clone()
+    }
+
+    #[cfg(not(unix))]
+    fn user(&mut self, _meta: &Metadata) -> String {
+        "-".to_string()
+    }
+
+    #[cfg(unix)]
+    fn group(&mut self, meta: &Metadata) -> String {
+        use std::os::unix::fs::MetadataExt;
+        let gid = meta.gid();
+        self.groups
+            .entry(gid)
+            .or_insert_with(|| group_name(gid).unwrap_or_else(|| gid.to_string()))
+            .clone()
+    }
+
+    #[cfg(not(unix))]
+    fn group(&mut self, _meta: &Metadata) -> String {
+        "-".to_string()
+    }
+}
+
+#[cfg(unix)]
+fn links(meta: &Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+    meta.nlink().to_string()
+}
+
+#[cfg(not(unix))]
+fn links(_meta: &Metadata) -> String {
+    "-".to_string()
+}
+
+#[cfg(unix)]
+fn inode(meta: &Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+    meta.ino().to_string()
+}
+
+#[cfg(not(unix))]
+fn inode(_meta: &Metadata) -> String {
+    "-".to_string()
+}
+
+impl Column {
+    // The name is drawn by the caller, so it is empty here.
+    pub fn value(
+        self,
+        meta: Option<&Metadata>,
+        items: Option<usize>,
+        exact_size: bool,
+        names: &mut Names,
+    ) -> String {
+        let meta = match meta {
+            Some(meta) => meta,
+            None => {
+                return match self {
+                    Column::Modified | Column::Created | Column::Accessed => format_time(None),
+                    _ => String::new(),
+                }
+            }
+        };
+        match self {
+            Column::Name => String::new(),
+            Column::Size => format_size(meta, items, exact_size),
+            Column::Modified => format_time(meta.modified().ok()),
+            Column::Created => format_time(meta.created().ok()),
+            Column::Accessed => format_time(meta.accessed().ok()),
+            Column::Permissions => format_permissions(meta),
+            Column::Owner => names.user(meta),
+            Column::Group => names.group(meta),
+            Column::Links => links(meta),
+            Column::Inode => inode(meta),
+        }
+    }
+
+    pub fn is_numeric(self) -> bool {
+        matches!(self, Column::Size | Column::Links | Column::Inode)
+    }
+
+    // Whether every value has the same width.
+    pub fn is_fixed_width(self) -> bool {
+        matches!(
+            self,
+            Column::Modified | Column::Created | Column::Accessed | Column::Permissions
+        )
+    }
+}
@@ -1,6 +1,7 @@
 use dirs::home_dir;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     error::Error,
     fs,
     io::BufReader,
@@ -8,6 +9,12 @@ use std::{
     process::Command,
 };
 
+use crate::{
+    column::{default_columns, Column},
+    keymap::{self, Keymap},
+    sort::Sort,
+};
+
 pub fn get_config_path() -> PathBuf {
     let mut path = home_dir().unwrap();
     path.push(".config");
@@ -22,11 +29,41 @@ struct ExecCommand {
     args: String,
 }
 
+impl ExecCommand {
+    // `%p` is replaced with the path and `%l` with the line number in each
+    // space-separated argument.
+    fn command(&self, path: &Path, line: usize, dir: &Path) -> Command {
+        let path = path.to_string_lossy();
+        let line = line.to_string();
+        let args = self
+            .args
+            .split_whitespace()
+            .map(|arg| arg.replace("%p", &path).replace("%l", &line));
+        let mut command = Command::new(&self.program);
+        command.current_dir(dir).args(args);
+        command
+    }
+
+    fn spawn(&self, path: &Path, line: usize, dir: &Path) {
+        let _ = self.command(path, line, dir).spawn();
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Config {
     exec_command: Option<ExecCommand>,
     edit_command: Option<ExecCommand>,
     bookmarks: Option<Vec<String>>,
+    max_jobs: Option<usize>,
+    sort: Option<Sort>,
+    show_hidden: Option<bool>,
+    respect_gitignore: Option<bool>,
+    exact_size: Option<bool>,
+    count_dir_items: Option<bool>,
+    columns: Option<Vec<Column>>,
+    keymap: Option<BTreeMap<keymap::Command, Vec<String>>>,
+    #[serde(skip)]
+    bindings: Keymap,
 }
 
 impl Config {
@@ -34,10 +71,13 @@ impl Config {
         let file = fs::File::open(path);
         if let Ok(file) = file {
             let reader = BufReader::new(file);
-            let config: Self = serde_json::from_reader(reader)?;
+            let mut config: Self = serde_json::from_reader(reader)?;
+            config.bindings = Keymap::new(config.keymap.as_ref().unwrap_or(&BTreeMap::new()))?;
             Ok(config)
         } else {
-            Ok(Config::default_self())
+            let mut config = Config::default_self();
+            config.bindings = Keymap::new(&BTreeMap::new())?;
+            Ok(config)
         }
     }
     pub fn default() -> Result<Self, Box<dyn Error>> {
@@ -67,6 +107,15 @@ impl Config {
             } else {
                 Some(bookmarks)
             },
+            max_jobs: None,
+            sort: None,
+            show_hidden: None,
+            respect_gitignore: None,
+            exact_size: None,
+            count_dir_items: None,
+            columns: None,
+            keymap: None,
+            bindings: Keymap::default(),
         }
     }
 
@@ -84,6 +133,15 @@ impl Config {
             } else {
                 Some(bookmarks)
             },
+            max_jobs: None,
+            sort: None,
+            show_hidden: None,
+            respect_gitignore: None,
+            exact_size: None,
+            count_dir_items: None,
+            columns: None,
+            keymap: None,
+            bindings: Keymap::default(),
         }
     }
 
@@ -91,25 +149,79 @@ impl Config {
         &self.bookmarks
     }
 
+    pub fn max_jobs(&self) -> usize {
+        self.max_jobs.unwrap_or(2)
+    }
+
+    pub fn sort(&self) -> Sort {
+        self.sort.unwrap_or_default()
+    }
+
+    pub fn show_hidden(&self) -> bool {
+        self.show_hidden.unwrap_or(false)
+    }
+
+    pub fn respect_gitignore(&self) -> bool {
+        self.respect_gitignore.unwrap_or(true)
+    }
+
+    pub fn exact_size(&self) -> bool {
+        self.exact_size.unwrap_or(false)
+    }
+
+    pub fn count_dir_items(&self) -> bool {
+        self.count_dir_items.unwrap_or(false)
+    }
+
+    // The name is always shown, first unless placed elsewhere.
+    pub fn columns(&self) -> Vec<Column> {
+        let mut columns = self.columns.clone().unwrap_or_else(default_columns);
+        if !columns.contains(&Column::Name) {
+            columns.insert(0, Column::Name);
+        }
+        columns
+    }
+
+    pub fn keymap(&self) -> &Keymap {
+        &self.bindings
+    }
+
     pub fn exec(&self, path: &Path, dir: &Path) {
         if let Some(command) = &self.exec_command {
-            let path = path.to_string_lossy().to_string();
-            let args = command.args.replace("%p", &path);
-            let _ = Command::new(&command.program)
-                .current_dir(dir)
-                .arg(args)
-                .spawn();
+            command.spawn(path, 1, dir);
         }
     }
 
     pub fn edit(&self, path: &Path, dir: &Path) {
+        self.edit_at(path, 1, dir);
+    }
+
+    pub fn edit_at(&self, path: &Path, line: usize, dir: &Path) {
         if let Some(command) = &self.edit_command {
-            let path = path.to_string_lossy().to_string();
-            let args = command.args.replace("%p", &path);
-            let _ = Command::new(&command.program)
-                .current_dir(dir)
-                .arg(args)
-                .spawn();
+            command.spawn(path, line, dir);
         }
     }
+
+    // An editor the caller waits for, so $EDITOR comes before edit_command.
+    pub fn editor(&self, path: &Path, dir: &Path) -> Command {
+        let editor = std::env::var("EDITOR")
+            .ok()
+            .filter(|e| !e.trim().is_empty());
+        let mut command = match (editor, &self.edit_command) {
+            (Some(editor), _) => {
+                let mut words = editor.split_whitespace();
+                let mut command = Command::new(words.next().unwrap_or_default());
+                command.args(words).arg(path);
+                command
+            }
+            (None, Some(edit_command)) => edit_command.command(path, 1, dir),
+            (None, None) => {
+                let mut command = Command::new("vi");
+                command.arg(path);
+                command
+            }
+        };
+        command.current_dir(dir);
+        command
+    }
 }
@@ -0,0 +1,138 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use std::{
+    io,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver},
+        Arc,
+    },
+    thread,
+};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::{
+    action::Action,
+    job::{format_bytes, measure_until},
+};
+
+pub fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let width = area.width.min(width);
+    let height = area.height.min(height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+// The total size of the entries, measured on a worker thread so that the
+// dialog shows up right away.
+struct Size {
+    rx: Receiver<(u64, u64)>,
+    cancel: Arc<AtomicBool>,
+    total: Option<(u64, u64)>,
+}
+
+impl Drop for Size {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+pub struct ConfirmDialog {
+    title: String,
+    lines: Vec<String>,
+    size: Option<Size>,
+}
+
+impl ConfirmDialog {
+    pub fn new(title: String, lines: Vec<String>) -> Self {
+        Self {
+            title,
+            lines,
+            size: None,
+        }
+    }
+
+    // Shows the total size of `paths` below the first line.
+    pub fn with_size(mut self, paths: Vec<PathBuf>) -> Self {
+        let (tx, rx) = channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        {
+            let cancel = Arc::clone(&cancel);
+            thread::spawn(move || {
+                let mut total = (0, 0);
+                for path in paths.iter() {
+                    match measure_until(path, &cancel) {
+                        Ok((bytes, files)) => {
+                            total.0 += bytes;
+                            total.1 += files;
+                        }
+                        Err(e) if e.kind() == io::ErrorKind::Interrupted => return,
+                        Err(_) => {}
+                    }
+                }
+                let _ = tx.send(total);
+            });
+        }
+        self.size = Some(Size {
+            rx,
+            cancel,
+            total: None,
+        });
+        self
+    }
+
+    pub fn on_tick(&mut self) -> bool {
+        match self.size {
+            Some(ref mut size) if size.total.is_none() => match size.rx.try_recv() {
+                Ok(total) => {
+                    size.total = Some(total);
+                    true
+                }
+                Err(_) => false,
+            },
+            _ => false,
+        }
+    }
+
+    pub fn on_event(&mut self, key: &KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Char('y') => Some(Action::EndConfirm(true)),
+            _ => Some(Action::EndConfirm(false)),
+        }
+    }
+
+    pub fn on_draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let mut text = self
+            .lines
+            .iter()
+            .map(|line| Spans::from(Span::raw(line.clone())))
+            .collect::<Vec<_>>();
+        if let Some(ref size) = self.size {
+            let line = match size.total {
+                Some((bytes, files)) => format!("{} files, {}", files, format_bytes(bytes)),
+                None => "measuring...".to_string(),
+            };
+            text.insert(text.len().min(1), Spans::from(Span::raw(line)));
+        }
+        text.push(Spans::from(Span::raw("")));
+        text.push(Spans::from(Span::raw("y: yes  other: no")));
+        let area = centered_rect(area, 72, text.len() as u16 + 2);
+        let paragraph = Paragraph::new(text).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(self.title.clone()),
+        );
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
+}
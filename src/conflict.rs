@@ -0,0 +1,82 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::{
+    action::Action,
+    confirm::centered_rect,
+    dir::get_modified,
+    job::{format_bytes, Resolution},
+};
+
+fn describe(path: &Path) -> String {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => format!("<DIR> {}", get_modified(Some(meta))),
+        Ok(meta) => format!("{} {}", format_bytes(meta.len()), get_modified(Some(meta))),
+        _ => "-".to_string(),
+    }
+}
+
+pub struct ConflictDialog {
+    id: usize,
+    src: PathBuf,
+    dest: PathBuf,
+}
+
+impl ConflictDialog {
+    pub fn new(id: usize, src: PathBuf, dest: PathBuf) -> Self {
+        Self { id, src, dest }
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn on_event(&mut self, key: &KeyEvent) -> Option<Action> {
+        let answer = match key.code {
+            KeyCode::Char('o') => Some((Resolution::Overwrite, false)),
+            KeyCode::Char('O') => Some((Resolution::Overwrite, true)),
+            KeyCode::Char('n') => Some((Resolution::OverwriteIfNewer, false)),
+            KeyCode::Char('N') => Some((Resolution::OverwriteIfNewer, true)),
+            KeyCode::Char('s') => Some((Resolution::Skip, false)),
+            KeyCode::Char('S') => Some((Resolution::Skip, true)),
+            KeyCode::Char('r') => Some((Resolution::Rename, false)),
+            KeyCode::Char('R') => Some((Resolution::Rename, true)),
+            KeyCode::Esc => None,
+            _ => return None,
+        };
+        Some(Action::ResolveConflict(self.id, answer))
+    }
+
+    pub fn on_draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let area = centered_rect(area, 72, 9);
+        let text = vec![
+            Spans::from(Span::raw(format!(
+                "{} already exists.",
+                self.dest.display()
+            ))),
+            Spans::from(Span::raw(format!("  src:  {}", describe(&self.src)))),
+            Spans::from(Span::raw(format!("  dest: {}", describe(&self.dest)))),
+            Spans::from(Span::raw("")),
+            Spans::from(Span::raw("o: overwrite  n: overwrite if newer")),
+            Spans::from(Span::raw("s: skip  r: rename  Esc: cancel job")),
+            Spans::from(Span::raw("(O/N/S/R: apply to all)")),
+        ];
+        let paragraph = Paragraph::new(text).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Conflict".to_string()),
+        );
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
+}
@@ -1,170 +1,658 @@
-use chrono::{DateTime, Local};
-use crossterm::event::{KeyCode, KeyEvent};
-use fs_extra::dir;
 use std::{
-    cmp::{min, Ordering},
-    ffi::OsString,
-    fs::{self, read_dir, DirEntry, Metadata},
+    cmp::{max, min, Ordering as CmpOrdering},
+    collections::HashSet,
+    fs::{self, read_dir, Metadata},
     io,
     path::{Path, PathBuf},
     rc::Rc,
-    sync::mpsc::Sender,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, Sender, TryRecvError},
+        Arc,
+    },
     thread,
+    time::{Duration, Instant},
 };
 use tui::{
     backend::Backend,
     layout::{Constraint, Rect},
-    style::{Modifier, Style},
-    widgets::{Block, Borders, Row, Table, TableState},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
     Frame,
 };
 
-use crate::{action::Action, config::Config};
+use crate::{
+    action::Action,
+    column::{format_time, Column, Names},
+    config::Config,
+    filter::Filter,
+    history::{History, Location},
+    keymap::Command,
+    search::Search,
+    sort::Sort,
+};
+
+const BATCH_INTERVAL: Duration = Duration::from_millis(50);
+// A listing that makes no progress for this long is given up.
+const LOAD_TIMEOUT: Duration = Duration::from_secs(10);
 
-fn get_file_name(name: &OsString, meta: &Metadata) -> String {
-    let name = name.to_string_lossy().to_string();
-    if meta.is_dir() {
+fn get_file_name(name: &str, is_dir: bool) -> String {
+    let name = name.to_string();
+    if is_dir {
         format!("{}/", name)
     } else {
         name
     }
 }
 
-fn get_modified(meta: Option<Metadata>) -> String {
-    meta.map(|meta| meta.modified())
-        .map(|date| match date {
-            Ok(date) => {
-                let localtime: DateTime<Local> = date.into();
-                Some(localtime.format("%Y-%m-%d %T").to_string())
-            }
-            _ => None,
-        })
-        .unwrap_or(None)
-        .unwrap_or("-------- --:--:--".to_string())
+pub fn get_modified(meta: Option<Metadata>) -> String {
+    format_time(meta.and_then(|meta| meta.modified().ok()))
 }
 
+// `name` is the file name, or the path relative to the directory in a panel.
+// The metadata is read once when the entries are loaded.
+#[derive(Clone)]
 struct Entry {
-    raw: DirEntry,
+    path: PathBuf,
+    name: String,
+    meta: Option<Metadata>,
+    items: Option<usize>,
     mark: bool,
 }
 
 impl Entry {
-    fn new(entry: DirEntry) -> Self {
+    fn new(path: PathBuf, name: String) -> Self {
+        let meta = fs::symlink_metadata(&path).ok();
         Self {
-            raw: entry,
+            path,
+            name,
+            meta,
+            items: None,
             mark: false,
         }
     }
 
     fn is_dir(&self) -> bool {
-        match self.raw.file_type() {
-            Ok(file_type) => file_type.is_dir(),
-            _ => false,
+        self.meta.as_ref().is_some_and(|meta| meta.is_dir())
+    }
+}
+
+// A listing of arbitrary paths, such as search results, in place of the
+// entries of the directory.
+struct Panel {
+    title: String,
+    paths: Vec<PathBuf>,
+}
+
+// Widens `widths` to fit the values of `entries`.
+fn fit_columns(
+    columns: &[Column],
+    exact_size: bool,
+    widths: &mut [usize],
+    names: &mut Names,
+    entries: &[Entry],
+) {
+    for (column, width) in columns.iter().zip(widths.iter_mut()) {
+        let count = if column.is_fixed_width() {
+            1
+        } else {
+            entries.len()
+        };
+        for entry in entries.iter().take(count) {
+            let value = column.value(entry.meta.as_ref(), entry.items, exact_size, names);
+            *width = max(*width, value.chars().count());
         }
     }
 }
 
-fn get_entries(path: &Path) -> io::Result<Vec<Entry>> {
-    let mut entries = read_dir(path)?
-        .filter_map(|entry| match entry {
-            Ok(entry) => Some(Entry::new(entry)),
-            _ => None,
-        })
-        .collect::<Vec<_>>();
-    entries.sort_by(default_sort);
-    Ok(entries)
+fn sort_entries(entries: &mut [Entry], sort: Sort) {
+    entries.sort_by(|a, b| sort.compare((&a.name, a.meta.as_ref()), (&b.name, b.meta.as_ref())));
 }
 
-fn default_sort(a: &Entry, b: &Entry) -> Ordering {
-    let a_is_dir = match a.raw.file_type() {
-        Ok(file_type) => file_type.is_dir(),
-        _ => false,
-    };
-    let b_is_dir = match b.raw.file_type() {
-        Ok(file_type) => file_type.is_dir(),
-        _ => false,
+// What to list, sent to the loading thread.
+struct Query {
+    path: PathBuf,
+    panel: Option<Vec<PathBuf>>,
+    sort: Sort,
+    show_hidden: bool,
+    count_items: bool,
+}
+
+impl Query {
+    fn accepts(&self, path: &Path) -> bool {
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        self.show_hidden || !hidden
+    }
+
+    fn entry(&self, path: PathBuf, name: String) -> Option<Entry> {
+        if !self.accepts(&path) {
+            return None;
+        }
+        let mut entry = Entry::new(path, name);
+        // Paths in a panel that no longer exist are dropped.
+        if self.panel.is_some() && entry.meta.is_none() {
+            return None;
+        }
+        if self.count_items && entry.is_dir() {
+            entry.items = read_dir(&entry.path).ok().map(|dir| dir.count());
+        }
+        Some(entry)
+    }
+}
+
+enum LoadEvent {
+    Meta(Option<Metadata>),
+    Entries(Vec<Entry>),
+    Failed(io::Error),
+}
+
+// Sends the entries in sorted batches, so that they can be merged as they come.
+// `Meta` comes first once the directory is opened, or `Failed` if it can't be.
+fn load(query: Query, tx: Sender<LoadEvent>, cancel: Arc<AtomicBool>) {
+    let candidates: Box<dyn Iterator<Item = (PathBuf, String)>> = match query.panel {
+        Some(ref paths) => Box::new(paths.iter().map(|p| {
+            let name = p.strip_prefix(&query.path).unwrap_or(p);
+            (p.clone(), name.to_string_lossy().to_string())
+        })),
+        None => match read_dir(&query.path) {
+            Ok(dir) => Box::new(dir.filter_map(|entry| entry.ok()).map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                (entry.path(), name)
+            })),
+            Err(e) => {
+                let _ = tx.send(LoadEvent::Failed(e));
+                return;
+            }
+        },
     };
-    if a_is_dir == b_is_dir {
-        a.raw.path().cmp(&b.raw.path())
-    } else if a_is_dir {
-        Ordering::Less
-    } else {
-        Ordering::Greater
+    let _ = tx.send(LoadEvent::Meta(fs::metadata(&query.path).ok()));
+    let mut batch = Vec::new();
+    let mut sent = Instant::now();
+    for (path, name) in candidates {
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        batch.extend(query.entry(path, name));
+        if sent.elapsed() >= BATCH_INTERVAL {
+            sort_entries(&mut batch, query.sort);
+            if tx
+                .send(LoadEvent::Entries(std::mem::take(&mut batch)))
+                .is_err()
+            {
+                return;
+            }
+            sent = Instant::now();
+        }
+    }
+    sort_entries(&mut batch, query.sort);
+    let _ = tx.send(LoadEvent::Entries(batch));
+}
+
+// How a change of directory goes into the history once it is made.
+enum Visit {
+    Push(Location),
+    Back(Location),
+    Forward(Location),
+}
+
+// A directory being opened. The pane moves there only once it can be read;
+// `resume` restarts the listing it was in the middle of if it can't.
+struct Opening {
+    path: PathBuf,
+    visit: Option<Visit>,
+    resume: bool,
+}
+
+// A listing being read on a worker thread. A new listing is shown as it
+// comes in; a refresh is collected in `buffer` and replaces the old one at
+// the end. `dirty` asks for one more refresh once it is done.
+struct Loading {
+    rx: Receiver<LoadEvent>,
+    cancel: Arc<AtomicBool>,
+    last: Instant,
+    buffer: Option<Vec<Entry>>,
+    select: Option<PathBuf>,
+    opening: Option<Opening>,
+    dirty: bool,
+}
+
+impl Drop for Loading {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
     }
 }
 
 pub struct Dir {
     config: Rc<Config>,
     path: PathBuf,
+    meta: Option<Metadata>,
     entries: Vec<Entry>,
+    // The entries hidden by the filter, kept with their marks.
+    filtered: Vec<Entry>,
     state: TableState,
+    offset: usize,
+    sort: Sort,
+    show_hidden: bool,
+    filter: Option<Filter>,
+    search: Option<Search>,
+    // The rows of the entries that match the search.
+    matches: Vec<usize>,
+    panel: Option<Panel>,
+    names: Names,
+    // The widths of the columns, fitted to all the loaded entries so that
+    // they don't change with scrolling.
+    widths: Vec<usize>,
+    loading: Option<Loading>,
+    status: Option<String>,
+    // A directory that couldn't be opened, for the message line.
+    error: Option<String>,
+    history: History,
 }
 
 impl Dir {
-    pub fn new(config: Rc<Config>, path: &Path) -> io::Result<Self> {
-        let entries = get_entries(path)?;
+    pub fn new(config: Rc<Config>, path: &Path) -> Self {
+        let sort = config.sort();
+        let show_hidden = config.show_hidden();
         let mut state = TableState::default();
         state.select(Some(0));
-        Ok(Self {
+        let mut dir = Self {
             config,
             path: path.into(),
-            entries,
+            meta: None,
+            entries: Vec::new(),
+            filtered: Vec::new(),
             state,
-        })
+            offset: 0,
+            sort,
+            show_hidden,
+            filter: None,
+            search: None,
+            matches: Vec::new(),
+            panel: None,
+            names: Names::default(),
+            widths: Vec::new(),
+            loading: None,
+            status: None,
+            error: None,
+            history: History::new(path),
+        };
+        dir.load(None);
+        dir
     }
-    pub fn new_with_index(config: Rc<Config>, path: &Path, index_path: &Path) -> io::Result<Self> {
-        let entries = get_entries(path)?;
-        let mut state = TableState::default();
-        let index = entries
-            .iter()
-            .position(|entry| entry.raw.path() == index_path)
-            .map(|i| i + 1)
-            .unwrap_or(0);
-        state.select(Some(index));
-        Ok(Self {
-            config,
+
+    // Keeps the settings of the pane, such as the sort order, but not the
+    // filter or the panel.
+    pub fn change_dir(&mut self, path: &Path, index_path: Option<&Path>) {
+        let visit = if path != self.path || self.is_panel() {
+            Some(Visit::Push(self.location()))
+        } else {
+            None
+        };
+        self.go(path, index_path.map(PathBuf::from), visit);
+    }
+
+    // Opens `path` on the worker thread; the pane stays where it is until
+    // the directory can be read.
+    fn go(&mut self, path: &Path, select: Option<PathBuf>, visit: Option<Visit>) {
+        let resume = self.loading.is_some();
+        let mut query = self.query();
+        query.path = path.into();
+        query.panel = None;
+        let mut loading = self.start_loading(query);
+        loading.select = select;
+        loading.opening = Some(Opening {
             path: path.into(),
-            entries,
-            state,
-        })
+            visit,
+            resume,
+        });
+        self.loading = Some(loading);
+    }
+
+    fn enter(&mut self, opening: Opening, meta: Option<Metadata>) {
+        match opening.visit {
+            Some(Visit::Push(from)) => self.history.push(from, &opening.path),
+            Some(Visit::Back(current)) => {
+                self.history.back(current);
+            }
+            Some(Visit::Forward(current)) => {
+                self.history.forward(current);
+            }
+            None => {}
+        }
+        self.path = opening.path;
+        self.filter = None;
+        self.search = None;
+        self.panel = None;
+        self.status = None;
+        self.clear_entries();
+        self.meta = meta;
+    }
+
+    fn location(&self) -> Location {
+        Location {
+            path: self.path.clone(),
+            selected: self.selected_path(),
+        }
+    }
+
+    fn history_back(&mut self) {
+        if let Some(location) = self.history.peek_back().cloned() {
+            let current = self.location();
+            self.go(
+                &location.path,
+                location.selected,
+                Some(Visit::Back(current)),
+            );
+        }
+    }
+
+    fn history_forward(&mut self) {
+        if let Some(location) = self.history.peek_forward().cloned() {
+            let current = self.location();
+            self.go(
+                &location.path,
+                location.selected,
+                Some(Visit::Forward(current)),
+            );
+        }
+    }
+
+    pub fn recent(&self) -> &[PathBuf] {
+        self.history.recent()
+    }
+
+    // Lists `paths` instead of the directory until the pane changes directory.
+    pub fn panelize(&mut self, title: String, paths: Vec<PathBuf>) {
+        let mut seen = HashSet::new();
+        let paths = paths
+            .into_iter()
+            .filter(|path| seen.insert(path.clone()))
+            .collect();
+        self.panel = Some(Panel { title, paths });
+        self.filter = None;
+        self.search = None;
+        self.load(None);
+    }
+
+    pub fn is_panel(&self) -> bool {
+        self.panel.is_some()
+    }
+
+    pub fn show_hidden(&self) -> bool {
+        self.show_hidden
+    }
+
+    pub fn filter(&self) -> Option<&Filter> {
+        self.filter.as_ref()
     }
 
     pub fn path(&self) -> PathBuf {
         self.path.clone()
     }
 
+    pub fn take_error(&mut self) -> Option<String> {
+        self.error.take()
+    }
+
+    fn query(&self) -> Query {
+        Query {
+            path: self.path.clone(),
+            panel: self.panel.as_ref().map(|panel| panel.paths.clone()),
+            sort: self.sort,
+            show_hidden: self.show_hidden,
+            count_items: self.config.count_dir_items(),
+        }
+    }
+
+    fn start_loading(&mut self, query: Query) -> Loading {
+        let (tx, rx) = channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        {
+            let cancel = Arc::clone(&cancel);
+            thread::spawn(move || load(query, tx, cancel));
+        }
+        Loading {
+            rx,
+            cancel,
+            last: Instant::now(),
+            buffer: None,
+            select: None,
+            opening: None,
+            dirty: false,
+        }
+    }
+
+    // Starts a new listing, moving the cursor to `select` when it comes in.
+    fn load(&mut self, select: Option<PathBuf>) {
+        let mut loading = self.start_loading(self.query());
+        loading.select = select;
+        self.loading = Some(loading);
+        self.status = None;
+        self.clear_entries();
+    }
+
+    fn clear_entries(&mut self) {
+        self.meta = None;
+        self.entries.clear();
+        self.filtered.clear();
+        self.update_matches();
+        self.widths = vec![0; self.config.columns().len()];
+        self.offset = 0;
+        self.state.select(Some(0));
+    }
+
+    // Reads the entries again with the current settings. The old listing
+    // stays until the new one is complete, and a listing still running is
+    // kept and read again once, when it is done.
     pub fn refresh(&mut self) {
-        let entries = get_entries(self.path.as_path()).unwrap_or_default();
-        let index = self.state.selected().unwrap_or_default();
-        let mut state = TableState::default();
-        state.select(Some(min(index, entries.len())));
-        self.entries = entries;
-        self.state = state;
-    }
-
-    pub fn on_event(&self, key: &KeyEvent) -> Option<Action> {
-        match key.code {
-            KeyCode::Char('j') => Some(Action::CursorDown),
-            KeyCode::Char('k') => Some(Action::CursorUp),
-            KeyCode::Char('h') => Some(Action::ChangeDirToParent(self.path.clone())),
-            KeyCode::Char('l') => self.on_change_dir(),
-            KeyCode::Char('g') => Some(Action::CursorToFirst),
-            KeyCode::Char('G') => Some(Action::CursorToLast),
-            KeyCode::Char(' ') => Some(Action::ToggleMark),
-            KeyCode::Enter => self.on_enter(),
-            KeyCode::Char('e') => self.on_edit(),
-            KeyCode::Char('r') => self.on_rename(),
+        if let Some(ref mut loading) = self.loading {
+            loading.dirty = true;
+            return;
+        }
+        let mut loading = self.start_loading(self.query());
+        loading.buffer = Some(Vec::new());
+        self.loading = Some(loading);
+        self.status = None;
+    }
+
+    // Waits for the directory being opened, and moves the pane there once
+    // it can be read.
+    fn on_opening(&mut self) -> bool {
+        let loading = match self.loading {
+            Some(ref mut loading) => loading,
+            None => return false,
+        };
+        let result = match loading.rx.try_recv() {
+            Ok(LoadEvent::Meta(meta)) => Ok(meta),
+            Ok(LoadEvent::Failed(e)) => Err(e.to_string()),
+            Ok(LoadEvent::Entries(_)) => return false,
+            Err(TryRecvError::Empty) if loading.last.elapsed() < LOAD_TIMEOUT => return false,
+            Err(_) => Err("timed out".to_string()),
+        };
+        let opening = match loading.opening.take() {
+            Some(opening) => opening,
+            None => return false,
+        };
+        // A refresh asked for meanwhile was for the directory left behind.
+        let dirty = std::mem::take(&mut loading.dirty);
+        match result {
+            Ok(meta) => {
+                self.enter(opening, meta);
+                self.on_tick();
+            }
+            Err(e) => {
+                match opening.visit {
+                    Some(Visit::Back(_)) => self.history.forget_back(&opening.path),
+                    Some(Visit::Forward(_)) => self.history.forget_forward(&opening.path),
+                    _ => {}
+                }
+                self.loading = None;
+                self.error = Some(format!("Err: {}: {}", opening.path.display(), e));
+                if opening.resume || dirty {
+                    self.refresh();
+                }
+            }
+        }
+        true
+    }
+
+    // Takes in what the loading thread has sent; returns true when the
+    // listing changed.
+    pub fn on_tick(&mut self) -> bool {
+        if let Some(Loading {
+            opening: Some(_), ..
+        }) = self.loading
+        {
+            return self.on_opening();
+        }
+        let loading = match self.loading {
+            Some(ref mut loading) => loading,
+            None => return false,
+        };
+        let mut changed = false;
+        let mut merged = false;
+        let mut done = false;
+        loop {
+            match loading.rx.try_recv() {
+                Ok(event) => {
+                    loading.last = Instant::now();
+                    changed = true;
+                    match event {
+                        LoadEvent::Meta(meta) => self.meta = meta,
+                        LoadEvent::Entries(batch) => match loading.buffer {
+                            Some(ref mut buffer) => buffer.extend(batch),
+                            None => {
+                                let selected = match self.state.selected() {
+                                    Some(0) | None => None,
+                                    Some(index) => Some(self.entries[index - 1].path.clone()),
+                                };
+                                fit_columns(
+                                    &self.config.columns(),
+                                    self.config.exact_size(),
+                                    &mut self.widths,
+                                    &mut self.names,
+                                    &batch,
+                                );
+                                let filter = self.filter.as_ref();
+                                let (batch, filtered): (Vec<_>, Vec<_>) =
+                                    batch.into_iter().partition(|entry| {
+                                        filter.is_none_or(|f| f.is_match(&entry.name))
+                                    });
+                                // Both are sorted, so these are merges.
+                                self.filtered.extend(filtered);
+                                sort_entries(&mut self.filtered, self.sort);
+                                self.entries.extend(batch);
+                                sort_entries(&mut self.entries, self.sort);
+                                merged = true;
+                                let target = loading.select.clone().or(selected);
+                                if let Some(index) = self
+                                    .entries
+                                    .iter()
+                                    .position(|entry| Some(&entry.path) == target.as_ref())
+                                {
+                                    self.state.select(Some(index + 1));
+                                    loading.select = None;
+                                }
+                            }
+                        },
+                        LoadEvent::Failed(e) => self.status = Some(e.to_string()),
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    done = true;
+                    break;
+                }
+            }
+        }
+        if done {
+            let dirty = loading.dirty;
+            if let Some(mut entries) = loading.buffer.take() {
+                sort_entries(&mut entries, self.sort);
+                let marks = self
+                    .entries
+                    .iter()
+                    .chain(self.filtered.iter())
+                    .filter(|entry| entry.mark)
+                    .map(|entry| &entry.path)
+                    .collect::<HashSet<_>>();
+                for entry in entries.iter_mut() {
+                    entry.mark = marks.contains(&entry.path);
+                }
+                let columns = self.config.columns();
+                self.widths = vec![0; columns.len()];
+                fit_columns(
+                    &columns,
+                    self.config.exact_size(),
+                    &mut self.widths,
+                    &mut self.names,
+                    &entries,
+                );
+                let old = self.selected_entry().cloned();
+                self.replace_entries(entries, old);
+            }
+            self.loading = None;
+            if dirty {
+                self.refresh();
+            }
+        } else if loading.last.elapsed() >= LOAD_TIMEOUT {
+            self.status = Some("timed out".to_string());
+            self.loading = None;
+            changed = true;
+        }
+        if merged {
+            self.update_matches();
+        }
+        changed
+    }
+
+    pub fn on_command(&self, command: Command, count: Option<usize>) -> Option<Action> {
+        match command {
+            Command::CursorDown => Some(Action::CursorDown(count.unwrap_or(1))),
+            Command::CursorUp => Some(Action::CursorUp(count.unwrap_or(1))),
+            Command::ChangeDirToParent => Some(self.on_parent()),
+            Command::ChangeDir => self.on_change_dir(),
+            Command::HistoryBack => Some(Action::HistoryBack),
+            Command::HistoryForward => Some(Action::HistoryForward),
+            Command::CursorToFirst => Some(match count {
+                Some(line) => Action::CursorToLine(line),
+                None => Action::CursorToFirst,
+            }),
+            Command::CursorToLast => Some(match count {
+                Some(line) => Action::CursorToLine(line),
+                None => Action::CursorToLast,
+            }),
+            Command::ToggleMark => Some(Action::ToggleMark),
+            Command::Execute => self.on_enter(),
+            Command::Edit => self.on_edit(),
+            Command::StartRename => self.on_rename(),
+            Command::CycleSort => Some(Action::SetSort(self.sort.next_key())),
+            Command::ReverseSort => Some(Action::SetSort(self.sort.reversed())),
+            Command::ToggleHidden => Some(Action::ToggleHidden),
+            Command::SearchNext => Some(Action::SearchNext),
+            Command::SearchPrev => Some(Action::SearchPrev),
             _ => None,
         }
     }
 
+    // In a panel, going up returns to the directory itself.
+    fn on_parent(&self) -> Action {
+        if self.is_panel() {
+            Action::ChangeDir(self.path.clone())
+        } else {
+            Action::ChangeDirToParent(self.path.clone())
+        }
+    }
     fn on_change_dir(&self) -> Option<Action> {
         match self.state.selected() {
             Some(0) => None,
             Some(index) => {
                 let entry = &self.entries[index - 1];
                 if entry.is_dir() {
-                    Some(Action::ChangeDir(entry.raw.path()))
+                    Some(Action::ChangeDir(entry.path.clone()))
                 } else {
                     None
                 }
@@ -174,13 +662,13 @@ impl Dir {
     }
     fn on_enter(&self) -> Option<Action> {
         match self.state.selected() {
-            Some(0) => Some(Action::ChangeDirToParent(self.path.clone())),
+            Some(0) => Some(self.on_parent()),
             Some(index) => {
                 let entry = &self.entries[index - 1];
                 if entry.is_dir() {
-                    Some(Action::ChangeDir(entry.raw.path()))
+                    Some(Action::ChangeDir(entry.path.clone()))
                 } else {
-                    Some(Action::Execute(entry.raw.path()))
+                    Some(Action::Execute(entry.path.clone()))
                 }
             }
             _ => None,
@@ -191,7 +679,7 @@ impl Dir {
             Some(0) => Some(Action::Edit(self.path().clone())),
             Some(index) => {
                 let entry = &self.entries[index - 1];
-                let path = entry.raw.path();
+                let path = entry.path.clone();
                 Some(Action::Edit(path))
             }
             _ => None,
@@ -202,7 +690,7 @@ impl Dir {
             Some(0) => None,
             Some(index) => {
                 let entry = &self.entries[index - 1];
-                let name = entry.raw.file_name();
+                let name = entry.path.file_name().unwrap_or_default();
                 let name = name.to_string_lossy().to_string();
                 Some(Action::StartRename(name))
             }
@@ -212,26 +700,50 @@ impl Dir {
 
     pub fn on_dispatch(&mut self, action: &Action) {
         match action {
-            Action::CursorDown => self.cursor_down(),
-            Action::CursorUp => self.cursor_up(),
+            Action::CursorDown(count) => self.cursor_down(*count),
+            Action::CursorUp(count) => self.cursor_up(*count),
             Action::CursorToFirst => self.cursor_to_first(),
             Action::CursorToLast => self.cursor_to_last(),
+            Action::CursorToLine(line) => self.cursor_to_line(*line),
             Action::ToggleMark => self.toggle_mark(),
+            Action::HistoryBack => self.history_back(),
+            Action::HistoryForward => self.history_forward(),
+            Action::SetSort(sort) => {
+                self.sort = *sort;
+                self.refresh();
+            }
+            Action::ToggleHidden => {
+                self.show_hidden = !self.show_hidden;
+                self.refresh();
+            }
+            Action::SearchNext => self.jump_to_match(true, false),
+            Action::SearchPrev => self.jump_to_match(false, false),
+            // The filter only narrows by name, so the loaded entries are
+            // filtered again rather than read again.
+            Action::SetFilter(filter) => {
+                self.filter = filter.clone();
+                let old = self.selected_entry().cloned();
+                let mut entries = std::mem::take(&mut self.entries);
+                entries.append(&mut self.filtered);
+                // Both are sorted, so this is a merge.
+                sort_entries(&mut entries, self.sort);
+                self.replace_entries(entries, old);
+            }
             Action::Execute(path) => self.config.exec(path.as_path(), self.path.as_path()),
             Action::Edit(path) => self.config.edit(path.as_path(), self.path.as_path()),
             _ => {}
         }
     }
 
-    fn cursor_down(&mut self) {
+    fn cursor_down(&mut self, count: usize) {
         if let Some(index) = self.state.selected() {
-            let index = min(index + 1, self.entries.len());
+            let index = min(index.saturating_add(count), self.entries.len());
             self.state.select(Some(index));
         }
     }
-    fn cursor_up(&mut self) {
+    fn cursor_up(&mut self, count: usize) {
         if let Some(index) = self.state.selected() {
-            let index = index.saturating_sub(1);
+            let index = index.saturating_sub(count);
             self.state.select(Some(index));
         }
     }
@@ -245,174 +757,325 @@ impl Dir {
             self.state.select(Some(self.entries.len()));
         }
     }
+    fn cursor_to_line(&mut self, line: usize) {
+        if self.state.selected().is_some() {
+            self.state.select(Some(min(line, self.entries.len())));
+        }
+    }
+    // Lists the sorted `entries` that pass the filter, keeping the cursor on
+    // `old`, the entry it was on. When that entry is gone, the cursor moves
+    // to the one that now takes its place.
+    fn replace_entries(&mut self, entries: Vec<Entry>, old: Option<Entry>) {
+        let filter = self.filter.as_ref();
+        let (entries, filtered): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .partition(|entry| filter.is_none_or(|f| f.is_match(&entry.name)));
+        let index = match old {
+            None => 0,
+            Some(old) => {
+                let position = entries
+                    .iter()
+                    .position(|entry| entry.path == old.path)
+                    .unwrap_or_else(|| {
+                        entries
+                            .iter()
+                            .position(|entry| {
+                                self.sort.compare(
+                                    (&entry.name, entry.meta.as_ref()),
+                                    (&old.name, old.meta.as_ref()),
+                                ) == CmpOrdering::Greater
+                            })
+                            .unwrap_or(entries.len())
+                    });
+                min(position + 1, entries.len())
+            }
+        };
+        self.entries = entries;
+        self.filtered = filtered;
+        self.update_matches();
+        self.state.select(Some(index));
+    }
+    fn selected_entry(&self) -> Option<&Entry> {
+        match self.state.selected() {
+            Some(0) | None => None,
+            Some(index) => self.entries.get(index - 1),
+        }
+    }
+    fn selected_path(&self) -> Option<PathBuf> {
+        self.selected_entry().map(|entry| entry.path.clone())
+    }
     fn toggle_mark(&mut self) {
         match self.state.selected() {
-            Some(0) => self.cursor_down(),
+            Some(0) => self.cursor_down(1),
             Some(index) => {
                 let entry = &mut self.entries[index - 1];
                 entry.mark = !entry.mark;
-                self.cursor_down();
+                self.cursor_down(1);
             }
             _ => {}
         }
     }
 
-    pub fn search(&mut self, pattern: &String) {
-        let index = self
-            .entries
-            .iter()
-            .position(|entry| {
-                let name = entry.raw.file_name().to_string_lossy().to_lowercase();
-                let pattern = pattern.to_lowercase();
-                name.starts_with(&pattern)
-            })
-            .map(|i| i + 1);
-        if let Some(index) = index {
-            self.state.select(Some(index));
-        }
+    // Stays on the current entry while it still matches.
+    pub fn search(&mut self, search: &Search) {
+        self.search = if search.is_empty() {
+            None
+        } else {
+            Some(search.clone())
+        };
+        self.update_matches();
+        self.jump_to_match(true, true);
     }
-    pub fn copy_marks(&mut self, tx: &Sender<String>, dest_dir: &Path) {
-        for entry in self.entries.iter_mut() {
-            if entry.mark {
-                entry.mark = false;
-                let tx = tx.clone();
-                let src = entry.raw.path();
-                if entry.is_dir() {
-                    let dest = PathBuf::from(dest_dir);
-                    thread::spawn(move || {
-                        let opt = dir::CopyOptions::default();
-                        match dir::copy(src, dest, &opt) {
-                            Err(e) => {
-                                let _ = tx.send(format!("Err: {}", e.to_string()));
-                            }
-                            _ => {
-                                let _ = tx.send(String::new());
-                            }
-                        }
-                    });
-                } else {
-                    let mut dest = PathBuf::from(dest_dir);
-                    dest.push(entry.raw.file_name());
-                    thread::spawn(move || match fs::copy(src, dest) {
-                        Err(e) => {
-                            let _ = tx.send(format!("Err: {}", e.to_string()));
-                        }
-                        _ => {
-                            let _ = tx.send(String::new());
-                        }
-                    });
-                }
-            }
-        }
+    pub fn clear_search(&mut self) {
+        self.search = None;
+        self.update_matches();
     }
-    pub fn move_marks(&mut self, tx: &Sender<String>, dest_dir: &Path) {
-        for entry in self.entries.iter_mut() {
-            if entry.mark {
-                entry.mark = false;
-                let tx = tx.clone();
-                let src = entry.raw.path();
-                let mut dest = PathBuf::from(dest_dir);
-                dest.push(entry.raw.file_name());
-                thread::spawn(move || match fs::rename(src, dest) {
-                    Err(e) => {
-                        let _ = tx.send(format!("Err: {}", e.to_string()));
-                    }
-                    _ => {
-                        let _ = tx.send(String::new());
-                    }
-                });
-            }
+    // Called whenever the search or the entries change.
+    fn update_matches(&mut self) {
+        self.matches = match self.search {
+            Some(ref search) => self
+                .entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| search.is_match(&entry.name))
+                .map(|(i, _)| i + 1)
+                .collect(),
+            None => Vec::new(),
+        };
+    }
+    fn jump_to_match(&mut self, forward: bool, inclusive: bool) {
+        let matches = &self.matches;
+        let index = self.state.selected().unwrap_or_default();
+        let found = if forward {
+            matches
+                .iter()
+                .find(|&&i| i > index || (inclusive && i == index))
+                .or_else(|| matches.first())
+        } else {
+            matches
+                .iter()
+                .rev()
+                .find(|&&i| i < index)
+                .or_else(|| matches.last())
+        };
+        if let Some(&i) = found {
+            self.state.select(Some(i));
         }
     }
-    pub fn delete_marks(&mut self, tx: &Sender<String>) {
-        for entry in self.entries.iter_mut() {
-            if entry.mark {
+    // The position of the cursor among the matches (0 when it is not on one)
+    // and the number of matches.
+    pub fn search_status(&self) -> Option<(usize, usize)> {
+        self.search.as_ref()?;
+        let index = self.state.selected().unwrap_or_default();
+        let current = self
+            .matches
+            .binary_search(&index)
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        Some((current, self.matches.len()))
+    }
+    pub fn marks(&self) -> Vec<PathBuf> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.mark)
+            .map(|entry| entry.path.clone())
+            .collect()
+    }
+    pub fn take_marks(&mut self) -> Vec<PathBuf> {
+        self.entries
+            .iter_mut()
+            .filter(|entry| entry.mark)
+            .map(|entry| {
                 entry.mark = false;
-                let tx = tx.clone();
-                let src = entry.raw.path();
-                if entry.is_dir() {
-                    thread::spawn(move || match fs::remove_dir_all(src) {
-                        Err(e) => {
-                            let _ = tx.send(format!("{}", e.to_string()));
-                        }
-                        _ => {
-                            let _ = tx.send(String::new());
-                        }
-                    });
-                } else {
-                    thread::spawn(move || match fs::remove_file(src) {
-                        Err(e) => {
-                            let _ = tx.send(format!("{}", e.to_string()));
-                        }
-                        _ => {
-                            let _ = tx.send(String::new());
-                        }
-                    });
-                }
-            }
-        }
+                entry.path.clone()
+            })
+            .collect()
     }
-    pub fn create_dir(&mut self, name: &String) {
+    pub fn rename_names(&self) -> Vec<String> {
+        let any_marked = self.entries.iter().any(|entry| entry.mark);
+        self.entries
+            .iter()
+            .filter(|entry| entry.mark || !any_marked)
+            .filter_map(|entry| entry.path.file_name()?.to_str().map(String::from))
+            .filter(|name| !name.contains('\n'))
+            .collect()
+    }
+    pub fn create_dir(&mut self, name: &str) -> io::Result<PathBuf> {
         let mut path = self.path.clone();
         path.push(name);
-        match fs::create_dir(path) {
-            Err(e) => eprintln!("{}", e.to_string()),
-            _ => {}
-        }
+        fs::create_dir(&path)?;
+        Ok(path)
     }
-    pub fn rename(&mut self, name: &String) {
+    pub fn rename(&mut self, name: &str) -> io::Result<Option<(PathBuf, PathBuf)>> {
         match self.state.selected() {
-            Some(0) => {}
+            Some(0) | None => Ok(None),
             Some(index) => {
-                let mut path = self.path().clone();
-                path.push(name);
-                let entry = &mut self.entries[index - 1];
-                match fs::rename(entry.raw.path(), path) {
-                    Err(e) => eprintln!("{}", e.to_string()),
-                    _ => {}
+                // The parent of the entry, which differs from the directory in a panel.
+                let from = self.entries[index - 1].path.clone();
+                let path = from.with_file_name(name);
+                if from == path {
+                    return Ok(None);
+                }
+                if fs::symlink_metadata(&path).is_ok() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("{} already exists", name),
+                    ));
                 }
+                fs::rename(&from, &path)?;
+                Ok(Some((from, path)))
             }
-            _ => {}
         }
     }
 
+    // Keeps the cursor within the rows that fit in `height`.
+    fn scroll(&mut self, height: usize) {
+        let selected = self.state.selected().unwrap_or_default();
+        if selected < self.offset {
+            self.offset = selected;
+        } else if height > 0 && selected >= self.offset + height {
+            self.offset = selected + 1 - height;
+        }
+        self.offset = min(self.offset, self.entries.len());
+    }
+
     pub fn on_draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect, is_src: bool) {
-        let modified = get_modified(fs::metadata(self.path.as_path()).ok());
-        let date_width = modified.len() as u16;
-
-        let mut list = vec![Row::new(vec!["..".to_string(), modified])];
-        list.extend(self.entries.iter().filter_map(|entry| {
-            if let Ok(meta) = entry.raw.metadata() {
-                let name = get_file_name(&entry.raw.file_name(), &meta);
-                let date = get_modified(Some(meta));
-                let row = Row::new(vec![name, date]);
-                Some(if entry.mark {
-                    row.style(Style::default().add_modifier(Modifier::REVERSED))
-                } else {
-                    row
+        // Only the visible rows are built; row 0 is "..".
+        let height = area.height.saturating_sub(2) as usize;
+        self.scroll(height);
+        let end = min(self.offset + height, self.entries.len() + 1);
+        let columns = self.config.columns();
+        let exact = self.config.exact_size();
+        let meta = self.meta.as_ref();
+        let names = &mut self.names;
+        let parent_values = columns
+            .iter()
+            .map(|column| match column {
+                Column::Size => String::new(),
+                _ => column.value(meta, None, exact, names),
+            })
+            .collect::<Vec<_>>();
+        let rows = self.entries[self.offset.saturating_sub(1)..end.saturating_sub(1)]
+            .iter()
+            .map(|entry| {
+                let values = columns
+                    .iter()
+                    .map(|column| column.value(entry.meta.as_ref(), entry.items, exact, names))
+                    .collect::<Vec<_>>();
+                (entry, values)
+            })
+            .collect::<Vec<_>>();
+        let column_widths = self
+            .widths
+            .iter()
+            .zip(&parent_values)
+            .map(|(width, value)| max(*width, value.chars().count()))
+            .collect::<Vec<_>>();
+        let cells = |name: Cell<'static>, values: Vec<String>| {
+            let mut name = Some(name);
+            columns
+                .iter()
+                .zip(values)
+                .zip(&column_widths)
+                .map(|((column, value), width)| match column {
+                    Column::Name => name.take().unwrap_or_default(),
+                    _ if column.is_numeric() => Cell::from(format!("{:>1$}", value, width)),
+                    _ => Cell::from(value),
                 })
+                .collect::<Vec<_>>()
+        };
+
+        let mut list = Vec::new();
+        if self.offset == 0 {
+            list.push(Row::new(cells(Cell::from(".."), parent_values)));
+        }
+        list.extend(rows.into_iter().map(|(entry, values)| {
+            let name = get_file_name(&entry.name, entry.is_dir());
+            let indices = self
+                .search
+                .as_ref()
+                .and_then(|search| search.indices(&entry.name));
+            let name = match indices {
+                Some(indices) => {
+                    let style = Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD);
+                    let spans = name
+                        .chars()
+                        .enumerate()
+                        .map(|(i, c)| {
+                            if indices.contains(&i) {
+                                Span::styled(c.to_string(), style)
+                            } else {
+                                Span::raw(c.to_string())
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    Cell::from(Spans::from(spans))
+                }
+                None => Cell::from(name),
+            };
+            let row = Row::new(cells(name, values));
+            if entry.mark {
+                row.style(Style::default().add_modifier(Modifier::REVERSED))
             } else {
-                None
+                row
             }
         }));
 
+        // The name column gets the width left by the others.
         let widths = {
-            let name_width = area.width - date_width - 3/* for borders */;
-            [
-                Constraint::Length(name_width),
-                Constraint::Length(date_width),
-            ]
+            let others = columns
+                .iter()
+                .zip(&column_widths)
+                .filter(|(column, _)| **column != Column::Name)
+                .map(|(_, width)| *width as u16 + 1 /* for spacing */)
+                .sum::<u16>();
+            let name_width = area.width.saturating_sub(others + 2 /* for borders */);
+            columns
+                .iter()
+                .zip(&column_widths)
+                .map(|(column, width)| match column {
+                    Column::Name => Constraint::Length(name_width),
+                    _ => Constraint::Length(*width as u16),
+                })
+                .collect::<Vec<_>>()
         };
-        let table = Table::new(list).widths(&widths).block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(self.path.to_string_lossy().to_string()),
-        );
+        let table =
+            Table::new(list)
+                .widths(&widths)
+                .block(Block::default().borders(Borders::ALL).title(format!(
+                    "{}{} [{}{}{}]{}",
+                    self.path.to_string_lossy(),
+                    match self.panel {
+                        Some(ref panel) => format!(" <{}>", panel.title),
+                        None => String::new(),
+                    },
+                    self.sort,
+                    if self.show_hidden { " .*" } else { "" },
+                    match self.filter {
+                        Some(ref filter) => format!(" {}", filter),
+                        None => String::new(),
+                    },
+                    match (&self.loading, &self.status) {
+                        (
+                            Some(Loading {
+                                opening: Some(_), ..
+                            }),
+                            _,
+                        ) => " opening...".to_string(),
+                        (Some(_), _) => format!(" loading... {}", self.entries.len()),
+                        (None, Some(status)) => format!(" ({})", status),
+                        (None, None) => String::new(),
+                    }
+                )));
         let table = if is_src {
             table.highlight_style(Style::default().add_modifier(Modifier::UNDERLINED))
         } else {
             table
         };
-        f.render_stateful_widget(table, area, &mut self.state);
+        let mut state = TableState::default();
+        state.select(self.state.selected().map(|index| index - self.offset));
+        f.render_stateful_widget(table, area, &mut state);
     }
 }
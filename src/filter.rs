@@ -0,0 +1,177 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use regex::{Regex, RegexBuilder};
+use std::fmt;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    text::{Span, Spans},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+use crate::action::Action;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FilterKind {
+    Substring,
+    Glob,
+    Regex,
+}
+
+impl FilterKind {
+    fn next(self) -> Self {
+        match self {
+            FilterKind::Substring => FilterKind::Glob,
+            FilterKind::Glob => FilterKind::Regex,
+            FilterKind::Regex => FilterKind::Substring,
+        }
+    }
+}
+
+impl fmt::Display for FilterKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterKind::Substring => write!(f, "substr"),
+            FilterKind::Glob => write!(f, "glob"),
+            FilterKind::Regex => write!(f, "regex"),
+        }
+    }
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut in_class = false;
+    for c in glob.chars() {
+        match c {
+            '*' if !in_class => regex.push_str(".*"),
+            '?' if !in_class => regex.push('.'),
+            '[' if !in_class => {
+                in_class = true;
+                regex.push('[');
+            }
+            ']' if in_class => {
+                in_class = false;
+                regex.push(']');
+            }
+            '!' if in_class && regex.ends_with('[') => regex.push('^'),
+            '\\' | '^' | '[' | '&' | '~' if in_class => {
+                regex.push('\\');
+                regex.push(c);
+            }
+            _ if in_class => regex.push(c),
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+// Substring and glob filters ignore case; a regex is used as written.
+#[derive(Clone)]
+pub struct Filter {
+    kind: FilterKind,
+    pattern: String,
+    regex: Regex,
+}
+
+impl Filter {
+    pub fn new(kind: FilterKind, pattern: &str) -> Result<Self, regex::Error> {
+        let regex = match kind {
+            FilterKind::Substring => RegexBuilder::new(&regex::escape(pattern))
+                .case_insensitive(true)
+                .build()?,
+            FilterKind::Glob => RegexBuilder::new(&glob_to_regex(pattern))
+                .case_insensitive(true)
+                .build()?,
+            FilterKind::Regex => Regex::new(pattern)?,
+        };
+        Ok(Self {
+            kind,
+            pattern: pattern.to_string(),
+            regex,
+        })
+    }
+
+    pub fn is_match(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.kind, self.pattern)
+    }
+}
+
+pub struct FilterLine {
+    kind: FilterKind,
+    pattern: String,
+    error: Option<String>,
+}
+
+impl FilterLine {
+    pub fn new(filter: Option<&Filter>) -> Self {
+        match filter {
+            Some(filter) => Self {
+                kind: filter.kind,
+                pattern: filter.pattern.clone(),
+                error: None,
+            },
+            None => Self {
+                kind: FilterKind::Substring,
+                pattern: String::new(),
+                error: None,
+            },
+        }
+    }
+
+    fn update(&mut self) -> Option<Action> {
+        if self.pattern.is_empty() {
+            self.error = None;
+            return Some(Action::SetFilter(None));
+        }
+        match Filter::new(self.kind, &self.pattern) {
+            Ok(filter) => {
+                self.error = None;
+                Some(Action::SetFilter(Some(filter)))
+            }
+            Err(_) => {
+                self.error = Some("invalid pattern".to_string());
+                None
+            }
+        }
+    }
+
+    pub fn on_event(&mut self, key: &KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Char(c) => {
+                self.pattern.push(c);
+                self.update()
+            }
+            KeyCode::Backspace => {
+                self.pattern.pop();
+                self.update()
+            }
+            KeyCode::Tab => {
+                self.kind = self.kind.next();
+                self.update()
+            }
+            KeyCode::Enter => Some(Action::EndFilter(true)),
+            KeyCode::Esc => Some(Action::EndFilter(false)),
+            _ => None,
+        }
+    }
+
+    pub fn on_draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let mut spans = vec![
+            Span::raw(format!("filter({}): ", self.kind)),
+            Span::raw(self.pattern.clone()),
+        ];
+        if let Some(ref error) = self.error {
+            spans.push(Span::raw(format!("  ({})", error)));
+        }
+        let paragraph = Paragraph::new(vec![Spans::from(spans)]);
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
+}
@@ -0,0 +1,290 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use ignore::WalkBuilder;
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, Sender, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::action::Action;
+
+const BATCH_INTERVAL: Duration = Duration::from_millis(50);
+
+fn walk(
+    root: PathBuf,
+    show_hidden: bool,
+    gitignore: bool,
+    tx: Sender<Vec<String>>,
+    cancel: Arc<AtomicBool>,
+) {
+    let walker = WalkBuilder::new(&root)
+        .hidden(!show_hidden)
+        .ignore(gitignore)
+        .git_ignore(gitignore)
+        .git_global(gitignore)
+        .git_exclude(gitignore)
+        .parents(gitignore)
+        .build();
+    let mut batch = Vec::new();
+    let mut sent = Instant::now();
+    for entry in walker {
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        let entry = match entry {
+            Ok(entry) if entry.depth() > 0 => entry,
+            _ => continue,
+        };
+        if let Ok(path) = entry.path().strip_prefix(&root) {
+            batch.push(path.to_string_lossy().to_string());
+        }
+        if sent.elapsed() >= BATCH_INTERVAL {
+            if tx.send(std::mem::take(&mut batch)).is_err() {
+                return;
+            }
+            sent = Instant::now();
+        }
+    }
+    let _ = tx.send(batch);
+}
+
+struct Match {
+    score: i64,
+    index: usize,
+    indices: Vec<usize>,
+}
+
+// Walks the subtree on a worker thread; the candidates are taken in on_tick
+// as they are found.
+pub struct Finder {
+    root: PathBuf,
+    rx: Receiver<Vec<String>>,
+    cancel: Arc<AtomicBool>,
+    walking: bool,
+    candidates: Vec<String>,
+    pattern: String,
+    matcher: SkimMatcherV2,
+    matches: Vec<Match>,
+    state: ListState,
+}
+
+impl Finder {
+    pub fn new(root: &Path, show_hidden: bool, gitignore: bool) -> Self {
+        let (tx, rx) = channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        {
+            let root = root.to_path_buf();
+            let cancel = Arc::clone(&cancel);
+            thread::spawn(move || walk(root, show_hidden, gitignore, tx, cancel));
+        }
+        Self {
+            root: root.into(),
+            rx,
+            cancel,
+            walking: true,
+            candidates: Vec::new(),
+            pattern: String::new(),
+            matcher: SkimMatcherV2::default().ignore_case(),
+            matches: Vec::new(),
+            state: ListState::default(),
+        }
+    }
+
+    fn match_candidate(&self, index: usize) -> Option<Match> {
+        if self.pattern.is_empty() {
+            return Some(Match {
+                score: 0,
+                index,
+                indices: Vec::new(),
+            });
+        }
+        self.matcher
+            .fuzzy_indices(&self.candidates[index], &self.pattern)
+            .map(|(score, indices)| Match {
+                score,
+                index,
+                indices,
+            })
+    }
+
+    fn rank(&mut self, from: usize) {
+        if from == 0 {
+            self.matches.clear();
+        }
+        let matches = (from..self.candidates.len())
+            .filter_map(|index| self.match_candidate(index))
+            .collect::<Vec<_>>();
+        self.matches.extend(matches);
+        // Without a pattern the candidates stay in the order they were found.
+        if !self.pattern.is_empty() {
+            let candidates = &self.candidates;
+            self.matches.sort_by(|a, b| {
+                b.score
+                    .cmp(&a.score)
+                    .then_with(|| candidates[a.index].len().cmp(&candidates[b.index].len()))
+                    .then_with(|| a.index.cmp(&b.index))
+            });
+        }
+        self.state.select(if self.matches.is_empty() {
+            None
+        } else {
+            Some(
+                self.state
+                    .selected()
+                    .unwrap_or(0)
+                    .min(self.matches.len() - 1),
+            )
+        });
+    }
+
+    pub fn on_tick(&mut self) -> bool {
+        if !self.walking {
+            return false;
+        }
+        let from = self.candidates.len();
+        let mut changed = false;
+        loop {
+            match self.rx.try_recv() {
+                Ok(batch) => {
+                    self.candidates.extend(batch);
+                    changed = true;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.walking = false;
+                    changed = true;
+                    break;
+                }
+            }
+        }
+        if self.candidates.len() > from {
+            self.rank(from);
+        }
+        changed
+    }
+
+    pub fn on_event(&mut self, key: &KeyEvent) -> Option<Action> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match (key.code, ctrl) {
+            (KeyCode::Down, _) | (KeyCode::Char('n'), true) => {
+                if let Some(index) = self.state.selected() {
+                    self.state
+                        .select(Some((index + 1).min(self.matches.len() - 1)));
+                }
+                None
+            }
+            (KeyCode::Up, _) | (KeyCode::Char('p'), true) => {
+                if let Some(index) = self.state.selected() {
+                    self.state.select(Some(index.saturating_sub(1)));
+                }
+                None
+            }
+            (KeyCode::Char(c), false) => {
+                self.pattern.push(c);
+                self.state.select(Some(0));
+                self.rank(0);
+                None
+            }
+            (KeyCode::Backspace, _) => {
+                self.pattern.pop();
+                self.state.select(Some(0));
+                self.rank(0);
+                None
+            }
+            (KeyCode::Enter, _) => {
+                let path = self
+                    .state
+                    .selected()
+                    .map(|index| self.root.join(&self.candidates[self.matches[index].index]));
+                Some(Action::CloseFinder(path))
+            }
+            (KeyCode::Char('o'), true) => {
+                let paths = self
+                    .matches
+                    .iter()
+                    .map(|m| self.root.join(&self.candidates[m.index]))
+                    .collect();
+                Some(Action::Panelize(format!("find {}", self.pattern), paths))
+            }
+            (KeyCode::Esc, _) => Some(Action::CloseFinder(None)),
+            _ => None,
+        }
+    }
+
+    pub fn on_draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+        let title = format!(
+            "Find in {} ({}/{}{})",
+            self.root.display(),
+            self.matches.len(),
+            self.candidates.len(),
+            if self.walking { ", walking..." } else { "" }
+        );
+        let text = vec![Spans::from(vec![
+            Span::raw("> "),
+            Span::raw(self.pattern.clone()),
+        ])];
+        let paragraph =
+            Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(Clear, chunks[0]);
+        f.render_widget(paragraph, chunks[0]);
+
+        // Only the rows around the selection are built.
+        let height = chunks[1].height.saturating_sub(2) as usize;
+        let selected = self.state.selected().unwrap_or(0);
+        let offset = selected.saturating_sub(height.saturating_sub(1));
+        let style = Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD);
+        let items = self
+            .matches
+            .iter()
+            .skip(offset)
+            .take(height)
+            .map(|m| {
+                let spans = self.candidates[m.index]
+                    .chars()
+                    .enumerate()
+                    .map(|(i, c)| {
+                        if m.indices.contains(&i) {
+                            Span::styled(c.to_string(), style)
+                        } else {
+                            Span::raw(c.to_string())
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                ListItem::new(Spans::from(spans))
+            })
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::UNDERLINED));
+        let mut state = ListState::default();
+        state.select(self.state.selected().map(|index| index - offset));
+        f.render_widget(Clear, chunks[1]);
+        f.render_stateful_widget(list, chunks[1], &mut state);
+    }
+}
+
+impl Drop for Finder {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
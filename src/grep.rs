@@ -0,0 +1,313 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ignore::WalkBuilder;
+use regex::Regex;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, Sender, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::action::Action;
+
+const BATCH_INTERVAL: Duration = Duration::from_millis(50);
+const MAX_RESULTS: usize = 10000;
+const MAX_SNIPPET: usize = 200;
+
+struct GrepResult {
+    path: PathBuf,
+    line: usize,
+    text: String,
+}
+
+// A file with a NUL byte in its first 8KB is treated as binary.
+fn is_binary(path: &Path) -> bool {
+    let mut buffer = [0u8; 8192];
+    match File::open(path).and_then(|mut file| file.read(&mut buffer)) {
+        Ok(len) => buffer[..len].contains(&0),
+        Err(_) => true,
+    }
+}
+
+fn grep_file(path: &Path, regex: &Regex, results: &mut Vec<GrepResult>) {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return,
+    };
+    let mut reader = BufReader::new(file);
+    let mut buffer = Vec::new();
+    let mut line = 0;
+    while let Ok(len) = reader.read_until(b'\n', &mut buffer) {
+        if len == 0 {
+            break;
+        }
+        line += 1;
+        let text = String::from_utf8_lossy(&buffer);
+        if regex.is_match(&text) {
+            results.push(GrepResult {
+                path: path.into(),
+                line,
+                text: text.trim().chars().take(MAX_SNIPPET).collect(),
+            });
+        }
+        buffer.clear();
+    }
+}
+
+struct Worker {
+    root: PathBuf,
+    regex: Regex,
+    show_hidden: bool,
+    gitignore: bool,
+    tx: Sender<Vec<GrepResult>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Worker {
+    fn run(self) {
+        let walker = WalkBuilder::new(&self.root)
+            .hidden(!self.show_hidden)
+            .ignore(self.gitignore)
+            .git_ignore(self.gitignore)
+            .git_global(self.gitignore)
+            .git_exclude(self.gitignore)
+            .parents(self.gitignore)
+            .build();
+        let mut batch = Vec::new();
+        let mut sent = Instant::now();
+        let mut found = 0;
+        for entry in walker {
+            if self.cancel.load(Ordering::Relaxed) || found >= MAX_RESULTS {
+                break;
+            }
+            let entry = match entry {
+                Ok(entry) if entry.file_type().is_some_and(|t| t.is_file()) => entry,
+                _ => continue,
+            };
+            if is_binary(entry.path()) {
+                continue;
+            }
+            let len = batch.len();
+            grep_file(entry.path(), &self.regex, &mut batch);
+            found += batch.len() - len;
+            if sent.elapsed() >= BATCH_INTERVAL {
+                if self.tx.send(std::mem::take(&mut batch)).is_err() {
+                    return;
+                }
+                sent = Instant::now();
+            }
+        }
+        let _ = self.tx.send(batch);
+    }
+}
+
+pub struct Grep {
+    root: PathBuf,
+    show_hidden: bool,
+    gitignore: bool,
+    regex_mode: bool,
+    pattern: String,
+    editing: bool,
+    error: Option<String>,
+    rx: Option<Receiver<Vec<GrepResult>>>,
+    cancel: Arc<AtomicBool>,
+    results: Vec<GrepResult>,
+    state: ListState,
+}
+
+impl Grep {
+    pub fn new(root: &Path, show_hidden: bool, gitignore: bool) -> Self {
+        Self {
+            root: root.into(),
+            show_hidden,
+            gitignore,
+            regex_mode: false,
+            pattern: String::new(),
+            editing: true,
+            error: None,
+            rx: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            results: Vec::new(),
+            state: ListState::default(),
+        }
+    }
+
+    fn start(&mut self) {
+        let pattern = if self.regex_mode {
+            self.pattern.clone()
+        } else {
+            regex::escape(&self.pattern)
+        };
+        let regex = match Regex::new(&pattern) {
+            Ok(regex) => regex,
+            Err(_) => {
+                self.error = Some("invalid pattern".to_string());
+                return;
+            }
+        };
+        self.cancel.store(true, Ordering::Relaxed);
+        self.cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = channel();
+        let worker = Worker {
+            root: self.root.clone(),
+            regex,
+            show_hidden: self.show_hidden,
+            gitignore: self.gitignore,
+            tx,
+            cancel: Arc::clone(&self.cancel),
+        };
+        thread::spawn(move || worker.run());
+        self.rx = Some(rx);
+        self.results.clear();
+        self.state.select(None);
+        self.error = None;
+        self.editing = false;
+    }
+
+    pub fn on_tick(&mut self) -> bool {
+        let rx = match self.rx {
+            Some(ref rx) => rx,
+            None => return false,
+        };
+        let mut changed = false;
+        loop {
+            match rx.try_recv() {
+                Ok(batch) => {
+                    self.results.extend(batch);
+                    changed = true;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.rx = None;
+                    changed = true;
+                    break;
+                }
+            }
+        }
+        if self.state.selected().is_none() && !self.results.is_empty() {
+            self.state.select(Some(0));
+        }
+        changed
+    }
+
+    pub fn on_event(&mut self, key: &KeyEvent) -> Option<Action> {
+        if self.editing {
+            match key.code {
+                KeyCode::Char(c) => self.pattern.push(c),
+                KeyCode::Backspace => {
+                    self.pattern.pop();
+                }
+                KeyCode::Tab => self.regex_mode = !self.regex_mode,
+                KeyCode::Enter if !self.pattern.is_empty() => self.start(),
+                KeyCode::Esc => return Some(Action::CloseGrep),
+                _ => {}
+            }
+            return None;
+        }
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                if let Some(index) = self.state.selected() {
+                    self.state
+                        .select(Some((index + 1).min(self.results.len() - 1)));
+                }
+                None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                if let Some(index) = self.state.selected() {
+                    self.state.select(Some(index.saturating_sub(1)));
+                }
+                None
+            }
+            KeyCode::Enter => self.state.selected().map(|index| {
+                let result = &self.results[index];
+                Action::EditAt(result.path.clone(), result.line)
+            }),
+            KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let paths = self.results.iter().map(|r| r.path.clone()).collect();
+                Some(Action::Panelize(format!("grep {}", self.pattern), paths))
+            }
+            KeyCode::Char('/') => {
+                self.editing = true;
+                None
+            }
+            KeyCode::Esc | KeyCode::Char('q') => Some(Action::CloseGrep),
+            _ => None,
+        }
+    }
+
+    pub fn on_draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+        let title = format!(
+            "Grep in {} ({})  Tab: {}",
+            self.root.display(),
+            if self.regex_mode { "regex" } else { "literal" },
+            if self.regex_mode { "literal" } else { "regex" },
+        );
+        let mut spans = vec![Span::raw("> "), Span::raw(self.pattern.clone())];
+        if let Some(ref error) = self.error {
+            spans.push(Span::raw(format!("  ({})", error)));
+        }
+        let paragraph = Paragraph::new(vec![Spans::from(spans)])
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(Clear, chunks[0]);
+        f.render_widget(paragraph, chunks[0]);
+
+        let title = format!(
+            "{} results{}  Enter: edit  Ctrl+o: panelize  /: pattern  Esc: close",
+            self.results.len(),
+            if self.rx.is_some() {
+                ", searching..."
+            } else {
+                ""
+            }
+        );
+        let height = chunks[1].height.saturating_sub(2) as usize;
+        let selected = self.state.selected().unwrap_or(0);
+        let offset = selected.saturating_sub(height.saturating_sub(1));
+        let items = self
+            .results
+            .iter()
+            .skip(offset)
+            .take(height)
+            .map(|result| {
+                let path = result.path.strip_prefix(&self.root).unwrap_or(&result.path);
+                ListItem::new(format!(
+                    "{}:{}: {}",
+                    path.display(),
+                    result.line,
+                    result.text
+                ))
+            })
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::UNDERLINED));
+        let mut state = ListState::default();
+        state.select(self.state.selected().map(|index| index - offset));
+        f.render_widget(Clear, chunks[1]);
+        f.render_stateful_widget(list, chunks[1], &mut state);
+    }
+}
+
+impl Drop for Grep {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
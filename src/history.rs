@@ -0,0 +1,114 @@
+use crossterm::event::KeyEvent;
+use std::path::{Path, PathBuf};
+use tui::{backend::Backend, layout::Rect, Frame};
+
+use crate::{action::Action, picker::PathPicker};
+
+// One more than the letters of the popup, which leaves out the current one.
+const MAX_RECENT: usize = 27;
+
+// A directory and the entry the cursor was on.
+#[derive(Clone)]
+pub struct Location {
+    pub path: PathBuf,
+    pub selected: Option<PathBuf>,
+}
+
+// Back and forward stacks of a pane, and the directories it visited, the
+// latest first.
+pub struct History {
+    back: Vec<Location>,
+    forward: Vec<Location>,
+    recent: Vec<PathBuf>,
+}
+
+impl History {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            back: Vec::new(),
+            forward: Vec::new(),
+            recent: vec![path.into()],
+        }
+    }
+
+    fn visit(&mut self, path: &Path) {
+        self.recent.retain(|recent| recent != path);
+        self.recent.insert(0, path.into());
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    pub fn push(&mut self, from: Location, to: &Path) {
+        self.back.push(from);
+        self.forward.clear();
+        self.visit(to);
+    }
+
+    // Where `back` would go, without going there yet.
+    pub fn peek_back(&self) -> Option<&Location> {
+        self.back.last()
+    }
+
+    pub fn peek_forward(&self) -> Option<&Location> {
+        self.forward.last()
+    }
+
+    // Drops the next location back or forward if it is `path`, which
+    // couldn't be opened, so that the ones beyond it can still be reached.
+    pub fn forget_back(&mut self, path: &Path) {
+        if self
+            .back
+            .last()
+            .is_some_and(|location| location.path == path)
+        {
+            self.back.pop();
+        }
+    }
+
+    pub fn forget_forward(&mut self, path: &Path) {
+        if self
+            .forward
+            .last()
+            .is_some_and(|location| location.path == path)
+        {
+            self.forward.pop();
+        }
+    }
+
+    pub fn back(&mut self, current: Location) -> Option<Location> {
+        let location = self.back.pop()?;
+        self.forward.push(current);
+        self.visit(&location.path);
+        Some(location)
+    }
+
+    pub fn forward(&mut self, current: Location) -> Option<Location> {
+        let location = self.forward.pop()?;
+        self.back.push(current);
+        self.visit(&location.path);
+        Some(location)
+    }
+
+    pub fn recent(&self) -> &[PathBuf] {
+        &self.recent
+    }
+}
+
+pub struct HistoryList {
+    picker: PathPicker,
+}
+
+impl HistoryList {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        Self {
+            picker: PathPicker::new("History", paths),
+        }
+    }
+
+    pub fn on_event(&mut self, key: &KeyEvent) -> Option<Action> {
+        Some(Action::CloseHistory(self.picker.pick(key)))
+    }
+
+    pub fn on_draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        self.picker.on_draw(f, area);
+    }
+}
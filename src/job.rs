@@ -0,0 +1,845 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Clear, Row, Table, TableState},
+    Frame,
+};
+
+use crate::{
+    journal::{Operation, Record},
+    trash,
+};
+
+const BUFFER_SIZE: usize = 64 * 1024;
+const NOTIFY_INTERVAL: Duration = Duration::from_millis(100);
+
+pub enum JobEvent {
+    Progress,
+    Conflict,
+    Finished(usize, String, Record),
+    // An undo or redo is over; the record goes back to the journal along
+    // with whether it was applied.
+    Replayed(usize, String, Record, bool),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum JobKind {
+    Copy,
+    Move,
+    Trash,
+    Delete,
+    Undo,
+    Redo,
+}
+
+impl JobKind {
+    fn name(&self) -> &'static str {
+        match self {
+            JobKind::Copy => "Copy",
+            JobKind::Move => "Move",
+            JobKind::Trash => "Trash",
+            JobKind::Delete => "Delete",
+            JobKind::Undo => "Undo",
+            JobKind::Redo => "Redo",
+        }
+    }
+}
+
+enum JobState {
+    Queued,
+    Running(Instant),
+}
+
+struct JobProgress {
+    state: JobState,
+    total_bytes: u64,
+    done_bytes: u64,
+    total_files: u64,
+    done_files: u64,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Resolution {
+    Overwrite,
+    OverwriteIfNewer,
+    Skip,
+    Rename,
+}
+
+enum Target {
+    Skip,
+    Write(PathBuf),
+    // The destination existed before and was cleared to be written over.
+    Overwrite(PathBuf),
+    Merge(PathBuf),
+}
+
+#[derive(Default)]
+struct JobControl {
+    paused: bool,
+    canceled: bool,
+    conflict: Option<(PathBuf, PathBuf)>,
+    answer: Option<(Resolution, bool)>,
+}
+
+pub struct Job {
+    id: usize,
+    kind: JobKind,
+    title: String,
+    sources: Vec<PathBuf>,
+    dest: Option<PathBuf>,
+    // The record to undo or redo.
+    record: Mutex<Option<Record>>,
+    progress: Mutex<JobProgress>,
+    control: Mutex<JobControl>,
+    resumed: Condvar,
+}
+
+impl Job {
+    fn new(
+        id: usize,
+        kind: JobKind,
+        title: String,
+        sources: Vec<PathBuf>,
+        dest: Option<PathBuf>,
+        record: Option<Record>,
+    ) -> Self {
+        Self {
+            id,
+            kind,
+            title,
+            sources,
+            dest,
+            record: Mutex::new(record),
+            progress: Mutex::new(JobProgress {
+                state: JobState::Queued,
+                total_bytes: 0,
+                done_bytes: 0,
+                total_files: 0,
+                done_files: 0,
+            }),
+            control: Mutex::new(JobControl::default()),
+            resumed: Condvar::new(),
+        }
+    }
+
+    fn cancel(&self) {
+        self.control.lock().unwrap().canceled = true;
+        self.resumed.notify_all();
+    }
+    fn toggle_pause(&self) {
+        let mut control = self.control.lock().unwrap();
+        control.paused = !control.paused;
+        self.resumed.notify_all();
+    }
+    fn is_paused(&self) -> bool {
+        self.control.lock().unwrap().paused
+    }
+    fn is_canceled(&self) -> bool {
+        self.control.lock().unwrap().canceled
+    }
+    fn checkpoint(&self) -> io::Result<()> {
+        let mut control = self.control.lock().unwrap();
+        while control.paused && !control.canceled {
+            control = self.resumed.wait(control).unwrap();
+        }
+        if control.canceled {
+            Err(io::Error::other("canceled"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn ask(
+        &self,
+        src: &Path,
+        dest: &Path,
+        tx: &Sender<JobEvent>,
+    ) -> io::Result<(Resolution, bool)> {
+        let mut control = self.control.lock().unwrap();
+        control.conflict = Some((src.into(), dest.into()));
+        control.answer = None;
+        let _ = tx.send(JobEvent::Conflict);
+        while control.answer.is_none() && !control.canceled {
+            control = self.resumed.wait(control).unwrap();
+        }
+        control.conflict = None;
+        match control.answer.take() {
+            Some(answer) if !control.canceled => Ok(answer),
+            _ => Err(io::Error::other("canceled")),
+        }
+    }
+    fn answer(&self, resolution: Resolution, apply_all: bool) {
+        let mut control = self.control.lock().unwrap();
+        control.conflict = None;
+        control.answer = Some((resolution, apply_all));
+        self.resumed.notify_all();
+    }
+
+    fn title(&self) -> String {
+        self.title.clone()
+    }
+}
+
+fn title(kind: JobKind, sources: &[PathBuf], dest: Option<&Path>) -> String {
+    let target = match sources {
+        [source] => source
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        sources => format!("{} items", sources.len()),
+    };
+    match dest {
+        Some(dest) => format!("{} {} -> {}", kind.name(), target, dest.display()),
+        None => format!("{} {}", kind.name(), target),
+    }
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{}{}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1}{}", size, UNITS[unit])
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+impl JobProgress {
+    fn summary(&self) -> (String, String) {
+        let started = match self.state {
+            JobState::Queued => return ("queued".to_string(), String::new()),
+            JobState::Running(started) => started,
+        };
+        let (done, total) = if self.total_bytes > 0 {
+            (self.done_bytes, self.total_bytes)
+        } else {
+            (self.done_files, self.total_files)
+        };
+        let percent = (done * 100).checked_div(total).unwrap_or(0);
+        let status = format!(
+            "{:>3}% {}/{} {}/{} files",
+            percent,
+            format_bytes(self.done_bytes),
+            format_bytes(self.total_bytes),
+            self.done_files,
+            self.total_files
+        );
+        let eta = if done > 0 && done < total {
+            let elapsed = started.elapsed().as_secs_f64();
+            let remaining = elapsed * (total - done) as f64 / done as f64;
+            format!(
+                "ETA {}",
+                format_duration(Duration::from_secs_f64(remaining))
+            )
+        } else {
+            String::new()
+        };
+        (status, eta)
+    }
+}
+
+struct Task<'a> {
+    job: &'a Job,
+    tx: &'a Sender<JobEvent>,
+    notified: Instant,
+    created: Vec<PathBuf>,
+    apply_all: Option<Resolution>,
+    operations: Vec<Operation>,
+}
+
+impl<'a> Task<'a> {
+    fn new(job: &'a Job, tx: &'a Sender<JobEvent>) -> Self {
+        Self {
+            job,
+            tx,
+            notified: Instant::now(),
+            created: Vec::new(),
+            apply_all: None,
+            operations: Vec::new(),
+        }
+    }
+
+    fn advance(&mut self, bytes: u64, files: u64) -> io::Result<()> {
+        {
+            let mut progress = self.job.progress.lock().unwrap();
+            progress.done_bytes += bytes;
+            progress.done_files += files;
+        }
+        if self.notified.elapsed() >= NOTIFY_INTERVAL {
+            self.notified = Instant::now();
+            let _ = self.tx.send(JobEvent::Progress);
+        }
+        self.job.checkpoint()
+    }
+
+    fn cleanup(&mut self) {
+        self.remove_created(0);
+    }
+
+    // Removes what was created since `self.created` had `len` entries.
+    fn remove_created(&mut self, len: usize) {
+        for path in self.created.drain(len..).rev() {
+            let _ = match fs::symlink_metadata(&path) {
+                Ok(meta) if meta.is_dir() => fs::remove_dir(&path),
+                Ok(_) => fs::remove_file(&path),
+                Err(e) => Err(e),
+            };
+        }
+    }
+
+    fn run(&mut self) -> io::Result<()> {
+        for source in self.job.sources.iter() {
+            let (bytes, files) = measure(source)?;
+            let mut progress = self.job.progress.lock().unwrap();
+            progress.total_bytes += bytes;
+            progress.total_files += files;
+        }
+        let _ = self.tx.send(JobEvent::Progress);
+
+        for source in self.job.sources.iter() {
+            self.job.checkpoint()?;
+            if let Some(dest) = &self.job.dest {
+                check_dest(source, dest)?;
+            }
+            match (self.job.kind, &self.job.dest) {
+                (JobKind::Copy, Some(dest)) => self.copy(source, &dest_path(source, dest)?)?,
+                (JobKind::Move, Some(dest)) => {
+                    self.move_entry(source, &dest_path(source, dest)?)?
+                }
+                (JobKind::Trash, _) => {
+                    let (bytes, files) = measure(source)?;
+                    let info = trash::trash(source)?;
+                    self.operations.push(Operation::Trash {
+                        path: source.clone(),
+                        info,
+                    });
+                    self.advance(bytes, files)?
+                }
+                (JobKind::Delete, _) => self.delete(source)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn target(&mut self, src: &Path, dest: &Path) -> io::Result<Target> {
+        let dest_meta = match fs::symlink_metadata(dest) {
+            Ok(meta) => meta,
+            _ => return Ok(Target::Write(dest.into())),
+        };
+        let src_meta = fs::symlink_metadata(src)?;
+        if src_meta.is_dir() && dest_meta.is_dir() {
+            return Ok(Target::Merge(dest.into()));
+        }
+        let resolution = match self.apply_all {
+            Some(resolution) => resolution,
+            None => {
+                let (resolution, apply_all) = self.job.ask(src, dest, self.tx)?;
+                if apply_all {
+                    self.apply_all = Some(resolution);
+                }
+                resolution
+            }
+        };
+        let overwrite = match resolution {
+            Resolution::Overwrite => true,
+            Resolution::OverwriteIfNewer => src_meta.modified()? > dest_meta.modified()?,
+            Resolution::Skip => false,
+            Resolution::Rename => return Ok(Target::Write(unique_path(dest))),
+        };
+        if !overwrite {
+            return Ok(Target::Skip);
+        }
+        if dest_meta.is_dir() {
+            fs::remove_dir_all(dest)?;
+        } else if !(dest_meta.is_file() && src_meta.is_file()) {
+            fs::remove_file(dest)?;
+        }
+        Ok(Target::Overwrite(dest.into()))
+    }
+
+    fn skip(&mut self, src: &Path) -> io::Result<()> {
+        let (bytes, files) = measure(src)?;
+        self.advance(bytes, files)
+    }
+
+    fn copy(&mut self, src: &Path, dest: &Path) -> io::Result<()> {
+        match self.target(src, dest)? {
+            Target::Skip => self.skip(src),
+            Target::Merge(dest) => {
+                for entry in fs::read_dir(src)? {
+                    let entry = entry?;
+                    self.copy(&entry.path(), &dest.join(entry.file_name()))?;
+                }
+                Ok(())
+            }
+            Target::Write(dest) => self.copy_to(src, &dest, true),
+            Target::Overwrite(dest) => self.copy_to(src, &dest, false),
+        }
+    }
+
+    // Only a new destination is recorded to be removed on cancel. A file
+    // written over is copied next to it first and renamed over it once
+    // complete, so that canceling leaves the original as it was.
+    fn copy_to(&mut self, src: &Path, dest: &Path, is_new: bool) -> io::Result<()> {
+        let dest = dest.to_path_buf();
+        let meta = fs::symlink_metadata(src)?;
+        if meta.file_type().is_symlink() {
+            copy_symlink(src, &dest)?;
+            if is_new {
+                self.created.push(dest);
+            }
+            self.advance(0, 1)?;
+        } else if meta.is_dir() {
+            fs::create_dir(&dest)?;
+            if is_new {
+                self.created.push(dest.clone());
+            }
+            for entry in fs::read_dir(src)? {
+                let entry = entry?;
+                self.copy(&entry.path(), &dest.join(entry.file_name()))?;
+            }
+            fs::set_permissions(&dest, meta.permissions())?;
+        } else if is_new {
+            self.created.push(dest.clone());
+            let result = File::create(&dest).and_then(|mut file| self.copy_file(src, &mut file));
+            if let Err(e) = result {
+                if self.job.is_canceled() {
+                    let _ = fs::remove_file(&dest);
+                }
+                return Err(e);
+            }
+            fs::set_permissions(&dest, meta.permissions())?;
+            self.advance(0, 1)?;
+        } else {
+            let dir = dest.parent().unwrap_or_else(|| Path::new("."));
+            // Removed when dropped, unless it has been renamed over `dest`.
+            let mut temp = tempfile::Builder::new()
+                .prefix(".dpfm-copy-")
+                .tempfile_in(dir)?;
+            self.copy_file(src, temp.as_file_mut())?;
+            fs::set_permissions(temp.path(), meta.permissions())?;
+            temp.persist(&dest).map_err(|e| e.error)?;
+            self.advance(0, 1)?;
+        }
+        Ok(())
+    }
+
+    fn copy_file(&mut self, src: &Path, writer: &mut File) -> io::Result<()> {
+        let mut reader = File::open(src)?;
+        let mut buffer = vec![0; BUFFER_SIZE];
+        loop {
+            let len = reader.read(&mut buffer)?;
+            if len == 0 {
+                break;
+            }
+            writer.write_all(&buffer[..len])?;
+            self.advance(len as u64, 0)?;
+        }
+        Ok(())
+    }
+
+    fn move_entry(&mut self, src: &Path, dest: &Path) -> io::Result<()> {
+        match self.target(src, dest)? {
+            Target::Skip => self.skip(src),
+            Target::Merge(dest) => {
+                for entry in fs::read_dir(src)? {
+                    let entry = entry?;
+                    self.move_entry(&entry.path(), &dest.join(entry.file_name()))?;
+                }
+                // Entries that were skipped stay behind in the source.
+                if fs::remove_dir(src).is_ok() {
+                    self.operations.push(Operation::RemoveDir(src.into()));
+                }
+                Ok(())
+            }
+            Target::Write(dest) => self.move_to(src, &dest, true),
+            Target::Overwrite(dest) => self.move_to(src, &dest, false),
+        }
+    }
+
+    fn move_to(&mut self, src: &Path, dest: &Path, is_new: bool) -> io::Result<()> {
+        let (bytes, files) = measure(src)?;
+        match fs::rename(src, dest) {
+            Ok(_) => self.advance(bytes, files)?,
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                self.move_across(src, dest, is_new)?
+            }
+            Err(e) => return Err(e),
+        }
+        self.operations.push(Operation::rename(src, dest));
+        Ok(())
+    }
+
+    fn move_across(&mut self, src: &Path, dest: &Path, is_new: bool) -> io::Result<()> {
+        let created = self.created.len();
+        if let Err(e) = self
+            .copy_to(src, dest, is_new)
+            .and_then(|_| verify(src, dest))
+        {
+            // The source is intact, so don't leave a partial copy behind.
+            self.remove_created(created);
+            return Err(e);
+        }
+        remove(src)?;
+        // The source is gone now, so canceling must not remove the copy.
+        self.created.truncate(created);
+        Ok(())
+    }
+
+    fn delete(&mut self, path: &Path) -> io::Result<()> {
+        let meta = fs::symlink_metadata(path)?;
+        if meta.is_dir() {
+            for entry in fs::read_dir(path)? {
+                self.delete(&entry?.path())?;
+            }
+            fs::remove_dir(path)?;
+        } else {
+            fs::remove_file(path)?;
+            self.advance(meta.len(), 1)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_dest(src: &Path, dest_dir: &Path) -> io::Result<()> {
+    let message = if src.parent() == Some(dest_dir) {
+        "source and destination are the same directory"
+    } else if dest_dir.starts_with(src) {
+        "cannot copy a directory into itself"
+    } else {
+        return Ok(());
+    };
+    Err(io::Error::new(io::ErrorKind::InvalidInput, message))
+}
+
+fn dest_path(src: &Path, dest_dir: &Path) -> io::Result<PathBuf> {
+    match src.file_name() {
+        Some(name) => Ok(dest_dir.join(name)),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid source path: {}", src.display()),
+        )),
+    }
+}
+
+fn unique_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, ext)))
+        .find(|path| fs::symlink_metadata(path).is_err())
+        .unwrap()
+}
+
+fn verify(src: &Path, dest: &Path) -> io::Result<()> {
+    if measure(src)? == measure(dest)? {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "{} does not match the source after copying",
+            dest.display()
+        )))
+    }
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+fn copy_tree(src: &Path, dest: &Path) -> io::Result<()> {
+    let meta = fs::symlink_metadata(src)?;
+    if meta.file_type().is_symlink() {
+        copy_symlink(src, dest)
+    } else if meta.is_dir() {
+        fs::create_dir(dest)?;
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            copy_tree(&entry.path(), &dest.join(entry.file_name()))?;
+        }
+        fs::set_permissions(dest, meta.permissions())
+    } else {
+        fs::copy(src, dest).map(|_| ())
+    }
+}
+
+pub fn move_path(src: &Path, dest: &Path) -> io::Result<()> {
+    match fs::rename(src, dest) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            if let Err(e) = copy_tree(src, dest).and_then(|_| verify(src, dest)) {
+                let _ = remove(dest);
+                return Err(e);
+            }
+            remove(src)
+        }
+        result => result,
+    }
+}
+
+pub fn measure(path: &Path) -> io::Result<(u64, u64)> {
+    measure_until(path, &AtomicBool::new(false))
+}
+
+// Gives up with `Interrupted` once `cancel` is set.
+pub fn measure_until(path: &Path, cancel: &AtomicBool) -> io::Result<(u64, u64)> {
+    if cancel.load(Ordering::Relaxed) {
+        return Err(io::ErrorKind::Interrupted.into());
+    }
+    let meta = fs::symlink_metadata(path)?;
+    if meta.is_dir() {
+        let mut total = (0, 0);
+        for entry in fs::read_dir(path)? {
+            let (bytes, files) = measure_until(&entry?.path(), cancel)?;
+            total.0 += bytes;
+            total.1 += files;
+        }
+        Ok(total)
+    } else if meta.file_type().is_symlink() {
+        Ok((0, 1))
+    } else {
+        Ok((meta.len(), 1))
+    }
+}
+
+#[cfg(unix)]
+fn copy_symlink(src: &Path, dest: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(src)?, dest)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(src: &Path, dest: &Path) -> io::Result<()> {
+    fs::copy(src, dest).map(|_| ())
+}
+
+// Undoes or redoes the record of the job. It is sent back even when the job
+// was canceled before it started, so that it isn't lost from the journal.
+fn replay(job: &Job, mut record: Record, tx: &Sender<JobEvent>) {
+    let (message, applied) = if job.is_canceled() {
+        (format!("{}: canceled.", job.title()), false)
+    } else {
+        job.progress.lock().unwrap().state = JobState::Running(Instant::now());
+        match record.apply(job.kind == JobKind::Redo) {
+            Ok(_) => (format!("{}: done.", job.title()), true),
+            Err(e) => (format!("Err: {}: {}", job.title(), e), false),
+        }
+    };
+    let _ = tx.send(JobEvent::Replayed(job.id, message, record, applied));
+}
+
+fn work(rx: Arc<Mutex<Receiver<Arc<Job>>>>, tx: Sender<JobEvent>) {
+    loop {
+        let job = match rx.lock().unwrap().recv() {
+            Ok(job) => job,
+            _ => break,
+        };
+        let record = job.record.lock().unwrap().take();
+        if let Some(record) = record {
+            replay(&job, record, &tx);
+            continue;
+        }
+        if job.is_canceled() {
+            continue;
+        }
+        job.progress.lock().unwrap().state = JobState::Running(Instant::now());
+        let mut task = Task::new(&job, &tx);
+        let message = match task.run() {
+            Ok(_) => format!("{}: done.", job.title()),
+            Err(_) if job.is_canceled() => {
+                task.cleanup();
+                format!("{}: canceled.", job.title())
+            }
+            Err(e) => format!("Err: {}: {}", job.title(), e),
+        };
+        let record = Record::new(job.title(), task.operations);
+        let _ = tx.send(JobEvent::Finished(job.id, message, record));
+    }
+}
+
+pub struct JobManager {
+    queue: Sender<Arc<Job>>,
+    jobs: Vec<Arc<Job>>,
+    next_id: usize,
+    state: TableState,
+}
+
+impl JobManager {
+    pub fn new(tx: Sender<JobEvent>, workers: usize) -> Self {
+        let (queue, rx) = channel::<Arc<Job>>();
+        let rx = Arc::new(Mutex::new(rx));
+        for _ in 0..workers.max(1) {
+            let rx = Arc::clone(&rx);
+            let tx = tx.clone();
+            thread::spawn(move || work(rx, tx));
+        }
+        Self {
+            queue,
+            jobs: Vec::new(),
+            next_id: 0,
+            state: TableState::default(),
+        }
+    }
+
+    pub fn push(&mut self, kind: JobKind, sources: Vec<PathBuf>, dest: Option<PathBuf>) {
+        if sources.is_empty() {
+            return;
+        }
+        let title = title(kind, &sources, dest.as_deref());
+        let job = Job::new(self.next_id, kind, title, sources, dest, None);
+        self.queue(job);
+    }
+
+    // Undoes (JobKind::Undo) or redoes (JobKind::Redo) the record.
+    pub fn push_replay(&mut self, kind: JobKind, record: Record) {
+        let title = format!("{} {}", kind.name(), record.name());
+        let job = Job::new(self.next_id, kind, title, Vec::new(), None, Some(record));
+        self.queue(job);
+    }
+
+    fn queue(&mut self, job: Job) {
+        let job = Arc::new(job);
+        self.next_id += 1;
+        self.jobs.push(Arc::clone(&job));
+        if self.state.selected().is_none() {
+            self.state.select(Some(0));
+        }
+        let _ = self.queue.send(job);
+    }
+
+    pub fn finish(&mut self, id: usize) {
+        self.jobs.retain(|job| job.id != id);
+        self.clamp_selection();
+    }
+
+    pub fn conflict(&self) -> Option<(usize, PathBuf, PathBuf)> {
+        self.jobs.iter().find_map(|job| {
+            let control = job.control.lock().unwrap();
+            control
+                .conflict
+                .clone()
+                .map(|(src, dest)| (job.id, src, dest))
+        })
+    }
+
+    pub fn resolve(&mut self, id: usize, answer: Option<(Resolution, bool)>) {
+        if let Some(job) = self.jobs.iter().find(|job| job.id == id) {
+            match answer {
+                Some((resolution, apply_all)) => job.answer(resolution, apply_all),
+                None => job.cancel(),
+            }
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if let Some(index) = self.state.selected() {
+            self.state.select(Some((index + 1) % self.jobs.len()));
+        }
+    }
+
+    pub fn cancel_selected(&mut self) {
+        if let Some(job) = self.selected() {
+            job.cancel();
+            if let JobState::Queued = job.progress.lock().unwrap().state {
+                let id = job.id;
+                self.finish(id);
+            }
+        }
+    }
+
+    pub fn toggle_pause_selected(&mut self) {
+        if let Some(job) = self.selected() {
+            job.toggle_pause();
+        }
+    }
+
+    fn selected(&self) -> Option<Arc<Job>> {
+        self.state
+            .selected()
+            .and_then(|index| self.jobs.get(index))
+            .map(Arc::clone)
+    }
+
+    fn clamp_selection(&mut self) {
+        let index = match self.state.selected() {
+            _ if self.jobs.is_empty() => None,
+            Some(index) => Some(index.min(self.jobs.len() - 1)),
+            None => Some(0),
+        };
+        self.state.select(index);
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn on_draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let list = self
+            .jobs
+            .iter()
+            .map(|job| {
+                let (status, eta) = job.progress.lock().unwrap().summary();
+                let eta = if job.is_paused() {
+                    "paused".to_string()
+                } else {
+                    eta
+                };
+                Row::new(vec![job.title(), status, eta])
+            })
+            .collect::<Vec<_>>();
+        let widths = {
+            let status_width = 36u16;
+            let eta_width = 12u16;
+            let title_width = area
+                .width
+                .saturating_sub(status_width + eta_width + 4 /* for borders */);
+            [
+                Constraint::Length(title_width),
+                Constraint::Length(status_width),
+                Constraint::Length(eta_width),
+            ]
+        };
+        let table = Table::new(list)
+            .widths(&widths)
+            .block(Block::default().borders(Borders::ALL).title("Jobs"))
+            .highlight_style(Style::default().add_modifier(Modifier::UNDERLINED));
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(table, area, &mut self.state);
+    }
+}
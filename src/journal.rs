@@ -0,0 +1,197 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{job::move_path, trash};
+
+const MAX_RECORDS: usize = 100;
+
+#[cfg(unix)]
+pub fn identity(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    fs::symlink_metadata(path).ok().map(|meta| meta.ino())
+}
+
+#[cfg(not(unix))]
+pub fn identity(_path: &Path) -> Option<u64> {
+    None
+}
+
+fn exists(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
+
+fn is_empty_dir(path: &Path) -> bool {
+    fs::read_dir(path)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(false)
+}
+
+fn changed(path: &Path) -> io::Error {
+    io::Error::other(format!("{} has changed", path.display()))
+}
+
+#[derive(Clone)]
+pub enum Operation {
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+        id: Option<u64>,
+    },
+    CreateDir(PathBuf),
+    RemoveDir(PathBuf),
+    Trash {
+        path: PathBuf,
+        info: PathBuf,
+    },
+}
+
+impl Operation {
+    pub fn rename(from: &Path, to: &Path) -> Self {
+        Operation::Rename {
+            from: from.into(),
+            to: to.into(),
+            id: identity(to),
+        }
+    }
+
+    fn check(&self, forward: bool) -> io::Result<()> {
+        let ok = match self {
+            Operation::Rename { from, to, id } => {
+                let (src, dest) = if forward { (from, to) } else { (to, from) };
+                if id.is_some() && identity(src) != *id {
+                    return Err(changed(src));
+                }
+                exists(src) && !exists(dest)
+            }
+            Operation::CreateDir(path) if forward => !exists(path),
+            Operation::CreateDir(path) => is_empty_dir(path),
+            Operation::RemoveDir(path) if forward => path.is_dir(),
+            Operation::RemoveDir(path) => !exists(path),
+            Operation::Trash { path, .. } if forward => exists(path),
+            Operation::Trash { path, info } => exists(info) && !exists(path),
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(changed(self.path()))
+        }
+    }
+
+    fn apply(&mut self, forward: bool) -> io::Result<()> {
+        match self {
+            Operation::Rename { from, to, id } => {
+                let (src, dest) = if forward { (from, to) } else { (to, from) };
+                move_path(src, dest)?;
+                // Moving across filesystems gives the entry a new identity.
+                *id = identity(dest);
+                Ok(())
+            }
+            Operation::CreateDir(path) if forward => fs::create_dir(path),
+            Operation::CreateDir(path) => fs::remove_dir(path),
+            Operation::RemoveDir(path) if forward => fs::remove_dir(path),
+            Operation::RemoveDir(path) => fs::create_dir(path),
+            Operation::Trash { path, info } if forward => {
+                *info = trash::trash(path)?;
+                Ok(())
+            }
+            Operation::Trash { info, .. } => trash::restore(info).map(|_| ()),
+        }
+    }
+
+    fn path(&self) -> &Path {
+        match self {
+            Operation::Rename { to, .. } => to,
+            Operation::CreateDir(path) | Operation::RemoveDir(path) => path,
+            Operation::Trash { path, .. } => path,
+        }
+    }
+}
+
+pub struct Record {
+    name: String,
+    operations: Vec<Operation>,
+}
+
+impl Record {
+    pub fn new(name: String, operations: Vec<Operation>) -> Self {
+        Self { name, operations }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn apply(&mut self, forward: bool) -> io::Result<()> {
+        let order = if forward {
+            (0..self.operations.len()).collect::<Vec<_>>()
+        } else {
+            (0..self.operations.len()).rev().collect::<Vec<_>>()
+        };
+        // Each operation is checked right before it is applied, since an
+        // earlier one may free the name a later one needs (a -> b, b -> a).
+        for (n, &i) in order.iter().enumerate() {
+            let result = self.operations[i]
+                .check(forward)
+                .and_then(|_| self.operations[i].apply(forward));
+            if let Err(e) = result {
+                for &i in order[..n].iter().rev() {
+                    let _ = self.operations[i].apply(!forward);
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+// The records are undone and redone by jobs, one at a time; `replaying` is
+// whether the running one is a redo.
+#[derive(Default)]
+pub struct Journal {
+    undo: Vec<Record>,
+    redo: Vec<Record>,
+    replaying: Option<bool>,
+}
+
+impl Journal {
+    pub fn push(&mut self, record: Record) {
+        if record.operations.is_empty() {
+            return;
+        }
+        self.undo.push(record);
+        if self.undo.len() > MAX_RECORDS {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self) -> Result<Record, &'static str> {
+        if self.replaying.is_some() {
+            return Err("Undo or redo is still running.");
+        }
+        let record = self.undo.pop().ok_or("Nothing to undo.")?;
+        self.replaying = Some(false);
+        Ok(record)
+    }
+
+    pub fn redo(&mut self) -> Result<Record, &'static str> {
+        if self.replaying.is_some() {
+            return Err("Undo or redo is still running.");
+        }
+        let record = self.redo.pop().ok_or("Nothing to redo.")?;
+        self.replaying = Some(true);
+        Ok(record)
+    }
+
+    // Takes back the record of the finished undo or redo.
+    pub fn replayed(&mut self, record: Record, applied: bool) {
+        let forward = self.replaying.take().unwrap_or_default();
+        if forward == applied {
+            self.undo.push(record);
+        } else {
+            self.redo.push(record);
+        }
+    }
+}
@@ -0,0 +1,355 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt,
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Command {
+    Quit,
+    CursorUp,
+    CursorDown,
+    CursorToFirst,
+    CursorToLast,
+    ToggleMark,
+    CycleSort,
+    ReverseSort,
+    ToggleHidden,
+    SwitchSrc,
+    DuplicateDir,
+    ChangeDir,
+    ChangeDirToParent,
+    HistoryBack,
+    HistoryForward,
+    OpenHistory,
+    Execute,
+    Edit,
+    StartSearch,
+    SearchNext,
+    SearchPrev,
+    StartFilter,
+    OpenFinder,
+    StartGrep,
+    Panelize,
+    Copy,
+    Move,
+    Trash,
+    Delete,
+    OpenTrash,
+    Undo,
+    Redo,
+    StartCreateDir,
+    StartRename,
+    BulkRename,
+    BatchRename,
+    OpenBookmarks,
+    SelectJob,
+    CancelJob,
+    PauseJob,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is already folded into the character itself ('G', '?').
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, Box<dyn Error>> {
+        let mut rest = s;
+        let mut modifiers = KeyModifiers::NONE;
+        loop {
+            let (modifier, len) = if starts_with_ignore_case(rest, "ctrl+") {
+                (KeyModifiers::CONTROL, 5)
+            } else if starts_with_ignore_case(rest, "alt+") {
+                (KeyModifiers::ALT, 4)
+            } else if starts_with_ignore_case(rest, "shift+") {
+                (KeyModifiers::SHIFT, 6)
+            } else {
+                break;
+            };
+            if rest.len() == len {
+                break;
+            }
+            modifiers |= modifier;
+            rest = &rest[len..];
+        }
+        let code = parse_key_code(rest).ok_or_else(|| format!("keymap: unknown key \"{}\"", s))?;
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl From<&KeyEvent> for KeyChord {
+    fn from(key: &KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+fn starts_with_ignore_case(s: &str, prefix: &str) -> bool {
+    s.len() >= prefix.len()
+        && s.is_char_boundary(prefix.len())
+        && s[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+fn parse_key_code(s: &str) -> Option<KeyCode> {
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    let code = match s.to_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "enter" => KeyCode::Enter,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "esc" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        name => match name.strip_prefix('f').map(|n| n.parse::<u8>()) {
+            Some(Ok(n)) if (1..=12).contains(&n) => KeyCode::F(n),
+            _ => return None,
+        },
+    };
+    Some(code)
+}
+
+fn default_bindings() -> Vec<(&'static str, Command)> {
+    vec![
+        ("q", Command::Quit),
+        ("j", Command::CursorDown),
+        ("k", Command::CursorUp),
+        ("h", Command::ChangeDirToParent),
+        ("l", Command::ChangeDir),
+        ("H", Command::HistoryBack),
+        ("L", Command::HistoryForward),
+        ("Alt+h", Command::OpenHistory),
+        ("g g", Command::CursorToFirst),
+        ("G", Command::CursorToLast),
+        ("Space", Command::ToggleMark),
+        ("s", Command::CycleSort),
+        ("S", Command::ReverseSort),
+        (".", Command::ToggleHidden),
+        ("Enter", Command::Execute),
+        ("e", Command::Edit),
+        ("r", Command::StartRename),
+        ("R", Command::BulkRename),
+        ("Alt+r", Command::BatchRename),
+        ("Tab", Command::SwitchSrc),
+        ("o", Command::DuplicateDir),
+        ("/", Command::StartSearch),
+        ("n", Command::SearchNext),
+        ("N", Command::SearchPrev),
+        ("f", Command::StartFilter),
+        ("F", Command::OpenFinder),
+        ("Ctrl+g", Command::StartGrep),
+        ("!", Command::Panelize),
+        ("c", Command::Copy),
+        ("m", Command::Move),
+        ("d d", Command::Trash),
+        ("D", Command::Delete),
+        ("t", Command::OpenTrash),
+        ("u", Command::Undo),
+        ("Ctrl+r", Command::Redo),
+        ("i", Command::StartCreateDir),
+        ("b", Command::OpenBookmarks),
+        ("J", Command::SelectJob),
+        ("X", Command::CancelJob),
+        ("p", Command::PauseJob),
+    ]
+}
+
+fn parse_sequence(s: &str) -> Result<Vec<KeyChord>, Box<dyn Error>> {
+    let keys = s
+        .split_whitespace()
+        .map(KeyChord::parse)
+        .collect::<Result<Vec<_>, _>>()?;
+    if keys.is_empty() {
+        return Err(format!("keymap: empty key sequence \"{}\"", s).into());
+    }
+    Ok(keys)
+}
+
+fn overlaps(a: &[KeyChord], b: &[KeyChord]) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
+
+pub enum Lookup {
+    Found(Command),
+    Pending,
+    NotFound,
+}
+
+#[derive(Default)]
+pub struct Keymap {
+    bindings: HashMap<Vec<KeyChord>, Command>,
+}
+
+impl Keymap {
+    pub fn new(custom: &BTreeMap<Command, Vec<String>>) -> Result<Self, Box<dyn Error>> {
+        let mut claimed = Vec::<(&String, Vec<KeyChord>, Command)>::new();
+        for (&command, sequences) in custom.iter() {
+            for sequence in sequences {
+                let keys = parse_sequence(sequence)?;
+                let conflict = claimed
+                    .iter()
+                    .find(|(_, other_keys, other)| {
+                        overlaps(other_keys, &keys) && !(*other_keys == keys && *other == command)
+                    })
+                    .map(|(other_sequence, other_keys, other)| {
+                        if *other_keys == keys {
+                            format!(
+                                "keymap: \"{}\" is bound to both {:?} and {:?}",
+                                sequence, other, command
+                            )
+                        } else if other_keys.len() < keys.len() {
+                            format!(
+                                "keymap: \"{}\" ({:?}) is a prefix of \"{}\" ({:?})",
+                                other_sequence, other, sequence, command
+                            )
+                        } else {
+                            format!(
+                                "keymap: \"{}\" ({:?}) is a prefix of \"{}\" ({:?})",
+                                sequence, command, other_sequence, other
+                            )
+                        }
+                    });
+                if let Some(conflict) = conflict {
+                    return Err(conflict.into());
+                }
+                claimed.push((sequence, keys, command));
+            }
+        }
+
+        let mut bindings = HashMap::new();
+        for (sequence, command) in default_bindings() {
+            let keys = parse_sequence(sequence)?;
+            if custom.contains_key(&command)
+                || claimed
+                    .iter()
+                    .any(|(_, other_keys, _)| overlaps(other_keys, &keys))
+            {
+                continue;
+            }
+            bindings.insert(keys, command);
+        }
+        for (_, keys, command) in claimed {
+            bindings.insert(keys, command);
+        }
+        Ok(Self { bindings })
+    }
+
+    pub fn lookup(&self, keys: &[KeyChord]) -> Lookup {
+        if let Some(&command) = self.bindings.get(keys) {
+            Lookup::Found(command)
+        } else if self.bindings.keys().any(|k| k.starts_with(keys)) {
+            Lookup::Pending
+        } else {
+            Lookup::NotFound
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PendingKeys {
+    count: Option<usize>,
+    keys: Vec<KeyChord>,
+}
+
+impl PendingKeys {
+    pub fn is_empty(&self) -> bool {
+        self.count.is_none() && self.keys.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.count = None;
+        self.keys.clear();
+    }
+
+    pub fn push(&mut self, keymap: &Keymap, key: &KeyEvent) -> Option<(Command, Option<usize>)> {
+        let chord = KeyChord::from(key);
+        if chord.code == KeyCode::Esc && !self.is_empty() {
+            self.clear();
+            return None;
+        }
+        if self.keys.is_empty() && chord.modifiers.is_empty() {
+            if let KeyCode::Char(c @ '0'..='9') = chord.code {
+                if c != '0' || self.count.is_some() {
+                    let digit = c as usize - '0' as usize;
+                    let count = self.count.unwrap_or(0);
+                    self.count = Some(count.saturating_mul(10).saturating_add(digit));
+                    return None;
+                }
+            }
+        }
+        self.keys.push(chord);
+        match keymap.lookup(&self.keys) {
+            Lookup::Found(command) => {
+                let count = self.count.take();
+                self.keys.clear();
+                Some((command, count))
+            }
+            Lookup::Pending => None,
+            Lookup::NotFound => {
+                self.clear();
+                None
+            }
+        }
+    }
+}
+
+impl fmt::Display for PendingKeys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(count) = self.count {
+            write!(f, "{}", count)?;
+        }
+        let keys = self
+            .keys
+            .iter()
+            .map(|key| key.to_string())
+            .collect::<Vec<_>>();
+        if keys.iter().all(|key| key.chars().count() == 1) {
+            write!(f, "{}", keys.join(""))
+        } else {
+            write!(f, "{}", keys.join(" "))
+        }
+    }
+}
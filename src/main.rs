@@ -6,9 +6,11 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use job::JobEvent;
 use std::{
     env::current_dir,
-    io::{stdout, Stdout},
+    io::{self, stdout, Stdout},
+    process::{Command, ExitStatus},
     rc::Rc,
     sync::mpsc::channel,
     time::Duration,
@@ -18,10 +20,26 @@ use tui::{backend::CrosstermBackend, Terminal};
 mod action;
 mod app;
 mod bookmark;
+mod column;
 mod config;
+mod confirm;
+mod conflict;
 mod dir;
+mod filter;
+mod finder;
+mod grep;
+mod history;
 mod input;
+mod job;
+mod journal;
+mod keymap;
+mod picker;
+mod rename;
 mod search;
+mod shell;
+mod sort;
+mod trash;
+mod watch;
 
 struct Main {
     terminal: Terminal<CrosstermBackend<Stdout>>,
@@ -36,6 +54,16 @@ impl Main {
         enable_raw_mode()?;
         Ok(Self { terminal })
     }
+
+    fn run(&mut self, process: &mut Command) -> io::Result<ExitStatus> {
+        let _ = disable_raw_mode();
+        let _ = execute!(self.terminal.backend_mut(), Show, LeaveAlternateScreen);
+        let status = process.status();
+        let _ = execute!(self.terminal.backend_mut(), EnterAlternateScreen, Hide);
+        let _ = enable_raw_mode();
+        let _ = self.terminal.clear();
+        status
+    }
 }
 
 impl Drop for Main {
@@ -46,10 +74,16 @@ impl Drop for Main {
 }
 
 fn main() {
-    let config = Rc::new(Config::default().unwrap());
+    let config = match Config::default() {
+        Ok(config) => Rc::new(config),
+        Err(e) => {
+            eprintln!("{}: {}", config::get_config_path().to_string_lossy(), e);
+            return;
+        }
+    };
     let path = current_dir().unwrap();
     let mut main = Main::new().unwrap();
-    let (tx, rx) = channel::<String>();
+    let (tx, rx) = channel::<JobEvent>();
     let mut app = app::App::new(config, tx, path.as_path()).unwrap();
 
     main.terminal
@@ -59,28 +93,36 @@ fn main() {
         .unwrap();
 
     loop {
-        let action: Option<Action> = if let Ok(message) = rx.try_recv() {
-            app.push_message(message);
-            Some(Action::Refresh)
-        } else if poll(Duration::from_millis(100)).unwrap_or(false) {
+        // Ticked on every iteration, so that loading keeps up while keys come in.
+        let ticked = app.on_tick();
+        let timeout = Duration::from_millis(if ticked { 0 } else { 100 });
+        let action: Option<Action> = if let Ok(event) = rx.try_recv() {
+            app.on_job_event(event)
+        } else if poll(timeout).unwrap_or(false) {
             match read() {
                 Ok(event) => match event {
                     Event::Key(key) => app.on_event(&key),
                     _ => None,
                 },
+                _ if ticked => None,
                 _ => continue,
             }
+        } else if ticked {
+            None
         } else {
             continue;
         };
 
         if let Some(action) = action {
             app.on_dispatch(&action);
-            match action {
-                Action::Quit => break,
-                _ => {}
+            if let Action::Quit = action {
+                break;
             }
         }
+        if let Some(mut process) = app.take_foreground() {
+            let status = main.run(&mut process);
+            app.on_foreground_exit(status);
+        }
 
         main.terminal
             .draw(|f| {
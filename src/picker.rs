@@ -0,0 +1,70 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use std::path::PathBuf;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Rect},
+    widgets::{Block, Borders, Clear, Row, Table},
+    Frame,
+};
+
+// One path for each letter.
+const MAX_PATHS: usize = 26;
+
+fn index_to_alphabet(i: usize) -> char {
+    (b'a' + i as u8) as char
+}
+fn alphabet_to_index(c: char) -> Option<usize> {
+    if c.is_ascii_lowercase() {
+        Some((c as u8 - b'a') as usize)
+    } else {
+        None
+    }
+}
+
+// A popup listing paths by letter, shared by the bookmarks and the history.
+pub struct PathPicker {
+    title: &'static str,
+    paths: Vec<PathBuf>,
+}
+
+impl PathPicker {
+    pub fn new(title: &'static str, mut paths: Vec<PathBuf>) -> Self {
+        paths.truncate(MAX_PATHS);
+        Self { title, paths }
+    }
+
+    // The path of the letter typed; any other key picks nothing.
+    pub fn pick(&self, key: &KeyEvent) -> Option<PathBuf> {
+        match key.code {
+            KeyCode::Char(c) => alphabet_to_index(c).and_then(|i| self.paths.get(i).cloned()),
+            _ => None,
+        }
+    }
+
+    pub fn on_draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let list = self
+            .paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| {
+                let letter = index_to_alphabet(i);
+                Row::new(vec![letter.to_string(), path.to_string_lossy().to_string()])
+            })
+            .collect::<Vec<_>>();
+        let widths = {
+            let letter_width = 2u16;
+            let path_width = area
+                .width
+                .saturating_sub(letter_width + 3 /* for borders */);
+            [
+                Constraint::Length(letter_width),
+                Constraint::Length(path_width),
+            ]
+        };
+        let table = Table::new(list)
+            .widths(&widths)
+            .block(Block::default().borders(Borders::ALL).title(self.title));
+        f.render_widget(Clear, area);
+        f.render_widget(table, area);
+    }
+}
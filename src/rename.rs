@@ -0,0 +1,472 @@
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Local,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+use regex::Regex;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+use tempfile::NamedTempFile;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph, Row, Table},
+    Frame,
+};
+
+use crate::{action::Action, journal::Operation};
+
+// The names to edit, one per line, in a temporary file. The file is created
+// new under a random name, readable only by the user, and removed on drop.
+pub struct RenameFile {
+    dir: PathBuf,
+    file: NamedTempFile,
+    names: Vec<String>,
+}
+
+impl RenameFile {
+    pub fn new(dir: &Path, names: Vec<String>) -> io::Result<Self> {
+        let mut file = tempfile::Builder::new()
+            .prefix("dpfm-rename-")
+            .suffix(".txt")
+            .tempfile()?;
+        let mut content = names.join("\n");
+        content.push('\n');
+        file.write_all(content.as_bytes())?;
+        file.flush()?;
+        Ok(Self {
+            dir: dir.into(),
+            file,
+            names,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+
+    // Read by path, since editors may save by replacing the file.
+    pub fn read(&self) -> io::Result<RenamePlan> {
+        let content = fs::read_to_string(self.file.path())?;
+        let lines = content.lines().collect::<Vec<_>>();
+        if lines.len() != self.names.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("expected {} lines, found {}", self.names.len(), lines.len()),
+            ));
+        }
+        let pairs = self
+            .names
+            .iter()
+            .zip(lines)
+            .map(|(from, to)| (from.clone(), to.to_string()))
+            .collect();
+        Ok(RenamePlan::new(&self.dir, pairs))
+    }
+}
+
+pub struct RenameItem {
+    pub from: String,
+    pub to: String,
+    pub problem: Option<&'static str>,
+}
+
+pub struct RenamePlan {
+    dir: PathBuf,
+    items: Vec<RenameItem>,
+}
+
+impl RenamePlan {
+    pub fn new(dir: &Path, pairs: Vec<(String, String)>) -> Self {
+        let sources = pairs
+            .iter()
+            .filter(|(from, to)| from != to)
+            .map(|(from, _)| from.clone())
+            .collect::<HashSet<_>>();
+        let mut targets = HashMap::<String, usize>::new();
+        for (_, to) in pairs.iter() {
+            *targets.entry(to.clone()).or_default() += 1;
+        }
+        let items = pairs
+            .into_iter()
+            .map(|(from, to)| {
+                let problem = if from == to {
+                    None
+                } else if to.is_empty() || to == "." || to == ".." || to.contains('/') {
+                    Some("invalid name")
+                } else if targets[&to] > 1 {
+                    Some("duplicate")
+                } else if !sources.contains(&to) && fs::symlink_metadata(dir.join(&to)).is_ok() {
+                    Some("exists")
+                } else {
+                    None
+                };
+                RenameItem { from, to, problem }
+            })
+            .collect();
+        Self {
+            dir: dir.into(),
+            items,
+        }
+    }
+
+    pub fn items(&self) -> &[RenameItem] {
+        &self.items
+    }
+
+    pub fn changes(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|item| item.from != item.to)
+            .count()
+    }
+
+    pub fn problems(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|item| item.problem.is_some())
+            .count()
+    }
+
+    // Renames whose target is still taken by a pending source wait for it;
+    // when only cycles (a -> b, b -> a) are left, one of them is moved to a
+    // temporary name first.
+    pub fn apply(&self) -> (Vec<Operation>, io::Result<()>) {
+        let mut pending = self
+            .items
+            .iter()
+            .filter(|item| item.from != item.to)
+            .map(|item| (self.dir.join(&item.from), self.dir.join(&item.to)))
+            .collect::<Vec<_>>();
+        let mut operations = Vec::new();
+        if self.problems() > 0 {
+            let e = io::Error::new(io::ErrorKind::InvalidInput, "the renames have conflicts");
+            return (operations, Err(e));
+        }
+        while !pending.is_empty() {
+            let ready = pending
+                .iter()
+                .position(|(_, to)| pending.iter().all(|(from, _)| from != to));
+            let (from, to) = match ready {
+                Some(index) => pending.remove(index),
+                None => {
+                    let (from, _) = &pending[0];
+                    let temp = (0..)
+                        .map(|n| {
+                            self.dir
+                                .join(format!(".rename-{}-{}", std::process::id(), n))
+                        })
+                        .find(|path| fs::symlink_metadata(path).is_err())
+                        .unwrap();
+                    (from.clone(), temp)
+                }
+            };
+            if let Err(e) = fs::rename(&from, &to) {
+                return (operations, Err(e));
+            }
+            operations.push(Operation::rename(&from, &to));
+            for (pending_from, _) in pending.iter_mut() {
+                if *pending_from == from {
+                    *pending_from = to.clone();
+                }
+            }
+        }
+        (operations, Ok(()))
+    }
+}
+
+pub struct RenamePreview {
+    plan: RenamePlan,
+}
+
+impl RenamePreview {
+    pub fn new(plan: RenamePlan) -> Self {
+        Self { plan }
+    }
+
+    pub fn plan(&self) -> &RenamePlan {
+        &self.plan
+    }
+
+    pub fn on_event(&mut self, key: &KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Char('y') if self.plan.problems() == 0 => Some(Action::EndRenamePreview(true)),
+            _ => Some(Action::EndRenamePreview(false)),
+        }
+    }
+
+    pub fn on_draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let title = if self.plan.problems() > 0 {
+            format!(
+                "Rename {} entries: {} conflicts, any key: cancel",
+                self.plan.changes(),
+                self.plan.problems()
+            )
+        } else {
+            format!("Rename {} entries? y: yes  other: no", self.plan.changes())
+        };
+        let items = self
+            .plan
+            .items()
+            .iter()
+            .filter(|item| item.from != item.to || item.problem.is_some())
+            .collect();
+        draw_items(f, area, title, items);
+    }
+}
+
+fn draw_items<B: Backend>(f: &mut Frame<B>, area: Rect, title: String, items: Vec<&RenameItem>) {
+    let list = items
+        .into_iter()
+        .map(|item| {
+            let row = Row::new(vec![
+                item.from.clone(),
+                item.to.clone(),
+                item.problem.unwrap_or_default().to_string(),
+            ]);
+            match item.problem {
+                Some(_) => row.style(Style::default().fg(Color::Red)),
+                None => row,
+            }
+        })
+        .collect::<Vec<_>>();
+    let widths = {
+        let problem_width = 12u16;
+        let name_width = area
+            .width
+            .saturating_sub(problem_width + 4 /* for borders */)
+            / 2;
+        [
+            Constraint::Length(name_width),
+            Constraint::Length(name_width),
+            Constraint::Length(problem_width),
+        ]
+    };
+    let table = Table::new(list)
+        .header(Row::new(vec!["Before", "After", ""]))
+        .widths(&widths)
+        .block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(Clear, area);
+    f.render_widget(table, area);
+}
+
+fn split_name(name: &str) -> (String, String) {
+    let path = Path::new(name);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path.extension().unwrap_or_default().to_string_lossy();
+    (stem.to_string(), ext.to_string())
+}
+
+fn expand(
+    template: &str,
+    name: &str,
+    n: usize,
+    modified: Option<&DateTime<Local>>,
+) -> Result<String, String> {
+    let (stem, ext) = split_name(name);
+    let mut expanded = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| "unclosed {".to_string())?;
+        let field = &rest[start + 1..end];
+        let (key, spec) = match field.find(':') {
+            Some(i) => (&field[..i], Some(&field[i + 1..])),
+            None => (field, None),
+        };
+        match (key, spec) {
+            ("name", None) => expanded.push_str(&stem),
+            ("ext", None) => expanded.push_str(&ext),
+            ("n", None) => expanded.push_str(&n.to_string()),
+            ("n", Some(spec)) => {
+                let width = spec
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid width {{{}}}", field))?;
+                if spec.starts_with('0') {
+                    expanded.push_str(&format!("{:01$}", n, width));
+                } else {
+                    expanded.push_str(&format!("{:1$}", n, width));
+                }
+            }
+            ("mtime", spec) => {
+                let items = StrftimeItems::new(spec.unwrap_or("%Y%m%d")).collect::<Vec<_>>();
+                if items.iter().any(|item| matches!(item, Item::Error)) {
+                    return Err(format!("invalid format {{{}}}", field));
+                }
+                let modified = modified.ok_or_else(|| format!("no mtime for {}", name))?;
+                let date = modified.format_with_items(items.into_iter());
+                expanded.push_str(&date.to_string());
+            }
+            _ => return Err(format!("unknown field {{{}}}", field)),
+        }
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum BatchMode {
+    Template,
+    Regex,
+}
+
+struct BatchEntry {
+    name: String,
+    modified: Option<DateTime<Local>>,
+}
+
+pub struct BatchRename {
+    dir: PathBuf,
+    entries: Vec<BatchEntry>,
+    mode: BatchMode,
+    input: String,
+    plan: RenamePlan,
+    error: Option<String>,
+}
+
+impl BatchRename {
+    pub fn new(dir: &Path, names: Vec<String>) -> Self {
+        let entries = names
+            .into_iter()
+            .map(|name| {
+                let modified = fs::symlink_metadata(dir.join(&name))
+                    .and_then(|meta| meta.modified())
+                    .ok()
+                    .map(DateTime::<Local>::from);
+                BatchEntry { name, modified }
+            })
+            .collect();
+        let mut batch = Self {
+            dir: dir.into(),
+            entries,
+            mode: BatchMode::Template,
+            input: String::new(),
+            plan: RenamePlan::new(dir, Vec::new()),
+            error: None,
+        };
+        batch.update();
+        batch
+    }
+
+    pub fn plan(&self) -> &RenamePlan {
+        &self.plan
+    }
+
+    fn rename_all(&self) -> Result<Vec<String>, String> {
+        let names = self.entries.iter().map(|entry| entry.name.clone());
+        if self.input.is_empty() {
+            return Ok(names.collect());
+        }
+        match self.mode {
+            BatchMode::Template => self
+                .entries
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| expand(&self.input, &entry.name, i + 1, entry.modified.as_ref()))
+                .collect(),
+            BatchMode::Regex => {
+                let (pattern, replacement) = match self.input.find('/') {
+                    Some(i) => (&self.input[..i], &self.input[i + 1..]),
+                    None => return Ok(names.collect()),
+                };
+                let regex = Regex::new(pattern).map_err(|e| e.to_string())?;
+                Ok(names
+                    .map(|name| regex.replace_all(&name, replacement).to_string())
+                    .collect())
+            }
+        }
+    }
+
+    fn update(&mut self) {
+        let (names, error) = match self.rename_all() {
+            Ok(names) => (names, None),
+            Err(e) => (
+                self.entries
+                    .iter()
+                    .map(|entry| entry.name.clone())
+                    .collect(),
+                Some(e),
+            ),
+        };
+        let pairs = self
+            .entries
+            .iter()
+            .map(|entry| entry.name.clone())
+            .zip(names)
+            .collect();
+        self.plan = RenamePlan::new(&self.dir, pairs);
+        self.error = error;
+    }
+
+    pub fn on_event(&mut self, key: &KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Char(c) => {
+                self.input.push(c);
+                self.update();
+                None
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+                self.update();
+                None
+            }
+            KeyCode::Tab => {
+                self.mode = match self.mode {
+                    BatchMode::Template => BatchMode::Regex,
+                    BatchMode::Regex => BatchMode::Template,
+                };
+                self.update();
+                None
+            }
+            KeyCode::Enter => {
+                if self.error.is_none() && self.plan.problems() == 0 && self.plan.changes() > 0 {
+                    Some(Action::EndBatchRename(true))
+                } else {
+                    None
+                }
+            }
+            KeyCode::Esc => Some(Action::EndBatchRename(false)),
+            _ => None,
+        }
+    }
+
+    pub fn on_draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+        let title = match self.mode {
+            BatchMode::Template => "Template {name} {ext} {n:03} {mtime:%Y%m%d}  Tab: regex",
+            BatchMode::Regex => "Regex pattern/replacement ($1)  Tab: template",
+        };
+        let text = vec![Spans::from(Span::raw(self.input.clone()))];
+        let paragraph =
+            Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(Clear, chunks[0]);
+        f.render_widget(paragraph, chunks[0]);
+
+        let title = match self.error {
+            Some(ref e) => format!("Err: {}", e),
+            None if self.plan.problems() > 0 => format!(
+                "Rename {} entries: {} conflicts",
+                self.plan.changes(),
+                self.plan.problems()
+            ),
+            None => format!(
+                "Rename {} entries  Enter: rename  Esc: cancel",
+                self.plan.changes()
+            ),
+        };
+        draw_items(f, chunks[1], title, self.plan.items().iter().collect());
+    }
+}
@@ -1,7 +1,9 @@
 use crossterm::event::{KeyCode, KeyEvent};
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use std::rc::Rc;
 use tui::{
     backend::Backend,
-    layout::Rect,
+    layout::{Alignment, Rect},
     text::{Span, Spans},
     widgets::{Clear, Paragraph},
     Frame,
@@ -9,38 +11,123 @@ use tui::{
 
 use crate::action::Action;
 
-pub struct SearchLine {
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SearchKind {
+    Substring,
+    Fuzzy,
+}
+
+// The matcher is built once and shared by the clones.
+#[derive(Clone)]
+pub struct Search {
+    kind: SearchKind,
     pattern: String,
+    matcher: Rc<SkimMatcherV2>,
+}
+
+fn eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+impl Search {
+    pub fn is_empty(&self) -> bool {
+        self.pattern.is_empty()
+    }
+
+    // The positions of the matched chars in `name`, case-insensitive.
+    pub fn indices(&self, name: &str) -> Option<Vec<usize>> {
+        if self.pattern.is_empty() {
+            return None;
+        }
+        match self.kind {
+            SearchKind::Substring => {
+                let name = name.chars().collect::<Vec<_>>();
+                let pattern = self.pattern.chars().collect::<Vec<_>>();
+                (0..=name.len().saturating_sub(pattern.len()))
+                    .find(|&start| {
+                        name.len() >= start + pattern.len()
+                            && pattern
+                                .iter()
+                                .zip(&name[start..])
+                                .all(|(&p, &c)| eq_ignore_case(p, c))
+                    })
+                    .map(|start| (start..start + pattern.len()).collect())
+            }
+            SearchKind::Fuzzy => self
+                .matcher
+                .fuzzy_indices(name, &self.pattern)
+                .map(|(_, indices)| indices),
+        }
+    }
+
+    pub fn is_match(&self, name: &str) -> bool {
+        self.indices(name).is_some()
+    }
+}
+
+pub struct SearchLine {
+    search: Search,
 }
 
 impl SearchLine {
     pub fn new() -> Self {
         Self {
-            pattern: String::new(),
+            search: Search {
+                kind: SearchKind::Substring,
+                pattern: String::new(),
+                matcher: Rc::new(SkimMatcherV2::default().ignore_case()),
+            },
         }
     }
 
     pub fn on_event(&mut self, key: &KeyEvent) -> Option<Action> {
         match key.code {
             KeyCode::Char(c) => {
-                self.pattern.push(c);
-                Some(Action::Search(self.pattern.clone()))
+                self.search.pattern.push(c);
+                Some(Action::Search(self.search.clone()))
             }
             KeyCode::Backspace => {
-                self.pattern.pop();
-                Some(Action::Search(self.pattern.clone()))
+                self.search.pattern.pop();
+                Some(Action::Search(self.search.clone()))
+            }
+            KeyCode::Tab => {
+                self.search.kind = match self.search.kind {
+                    SearchKind::Substring => SearchKind::Fuzzy,
+                    SearchKind::Fuzzy => SearchKind::Substring,
+                };
+                Some(Action::Search(self.search.clone()))
             }
-            _ => Some(Action::EndSearch),
+            KeyCode::Enter => Some(Action::EndSearch(true)),
+            KeyCode::Esc => Some(Action::EndSearch(false)),
+            _ => None,
         }
     }
 
-    pub fn on_draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+    pub fn on_draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+        area: Rect,
+        status: Option<(usize, usize)>,
+    ) {
         let text = vec![Spans::from(vec![
             Span::raw("/"),
-            Span::raw(self.pattern.clone()),
+            Span::raw(self.search.pattern.clone()),
         ])];
         let paragraph = Paragraph::new(text);
         f.render_widget(Clear, area);
         f.render_widget(paragraph, area);
+
+        let kind = match self.search.kind {
+            SearchKind::Substring => "substr",
+            SearchKind::Fuzzy => "fuzzy",
+        };
+        let status = match status {
+            Some((0, total)) => format!("{} -/{}", kind, total),
+            Some((current, total)) => format!("{} {}/{}", kind, current, total),
+            None => kind.to_string(),
+        };
+        let paragraph =
+            Paragraph::new(vec![Spans::from(Span::raw(status))]).alignment(Alignment::Right);
+        f.render_widget(paragraph, area);
     }
 }
@@ -0,0 +1,125 @@
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Child, Command, Output, Stdio},
+    sync::mpsc::{channel, Receiver},
+    thread,
+};
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    use std::os::unix::process::CommandExt;
+    let mut process = Command::new("sh");
+    process.arg("-c").arg(command);
+    // Its own process group, so that canceling stops what it started too.
+    process.process_group(0);
+    process
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut process = Command::new("cmd");
+    process.arg("/C").arg(command);
+    process
+}
+
+#[cfg(unix)]
+fn kill(child: &mut Child) {
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill(child: &mut Child) {
+    let _ = child.kill();
+}
+
+// Reads all of `pipe` on a worker thread.
+fn read_to_end<R: Read + Send + 'static>(pipe: Option<R>) -> Receiver<Vec<u8>> {
+    let (tx, rx) = channel();
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut output);
+        }
+        let _ = tx.send(output);
+    });
+    rx
+}
+
+// A shell command run in `dir`, whose output is read on worker threads.
+// Dropping it before it exits kills it.
+pub struct ShellCommand {
+    command: String,
+    dir: PathBuf,
+    child: Child,
+    rx: Receiver<Vec<u8>>,
+    error_rx: Receiver<Vec<u8>>,
+    output: Option<Vec<u8>>,
+    error: Option<Vec<u8>>,
+    exited: bool,
+}
+
+impl ShellCommand {
+    pub fn spawn(command: &str, dir: &Path) -> std::io::Result<Self> {
+        let mut child = shell(command)
+            .current_dir(dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            // Kept off the terminal, which the TUI is drawing on.
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let rx = read_to_end(child.stdout.take());
+        let error_rx = read_to_end(child.stderr.take());
+        Ok(Self {
+            command: command.into(),
+            dir: dir.into(),
+            child,
+            rx,
+            error_rx,
+            output: None,
+            error: None,
+            exited: false,
+        })
+    }
+
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    // The exit status and what was written, once the command has exited.
+    pub fn on_tick(&mut self) -> Option<std::io::Result<Output>> {
+        if self.output.is_none() {
+            self.output = Some(self.rx.try_recv().ok()?);
+        }
+        if self.error.is_none() {
+            self.error = Some(self.error_rx.try_recv().ok()?);
+        }
+        match self.child.try_wait() {
+            Ok(Some(status)) => {
+                self.exited = true;
+                Some(Ok(Output {
+                    status,
+                    stdout: self.output.take().unwrap_or_default(),
+                    stderr: self.error.take().unwrap_or_default(),
+                }))
+            }
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+impl Drop for ShellCommand {
+    fn drop(&mut self) {
+        if !self.exited {
+            kill(&mut self.child);
+            let _ = self.child.wait();
+        }
+    }
+}
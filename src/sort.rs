@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, fmt, fs::Metadata, iter::Peekable, path::Path, str::Chars};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SortKey {
+    Name,
+    Extension,
+    Size,
+    Modified,
+    Created,
+}
+
+impl SortKey {
+    fn next(self) -> Self {
+        match self {
+            SortKey::Name => SortKey::Extension,
+            SortKey::Extension => SortKey::Size,
+            SortKey::Size => SortKey::Modified,
+            SortKey::Modified => SortKey::Created,
+            SortKey::Created => SortKey::Name,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Sort {
+    pub key: SortKey,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+impl Default for Sort {
+    fn default() -> Self {
+        Self {
+            key: SortKey::Name,
+            descending: false,
+        }
+    }
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = match self.key {
+            SortKey::Name => "name",
+            SortKey::Extension => "ext",
+            SortKey::Size => "size",
+            SortKey::Modified => "mtime",
+            SortKey::Created => "ctime",
+        };
+        write!(f, "{}{}", key, if self.descending { "↓" } else { "↑" })
+    }
+}
+
+fn take_digits(chars: &mut Peekable<Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+        digits.push(*c);
+        chars.next();
+    }
+    digits
+}
+
+// Case-insensitive, with runs of digits compared by value ("file_2" < "file_0010").
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+    loop {
+        let ordering = match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_digits(&mut a_chars);
+                let y = take_digits(&mut b_chars);
+                let x_value = x.trim_start_matches('0');
+                let y_value = y.trim_start_matches('0');
+                x_value
+                    .len()
+                    .cmp(&y_value.len())
+                    .then_with(|| x_value.cmp(y_value))
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                a_chars.next();
+                b_chars.next();
+                ordering
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+fn extension(name: &str) -> String {
+    Path::new(name)
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+impl Sort {
+    pub fn next_key(self) -> Self {
+        Self {
+            key: self.key.next(),
+            ..self
+        }
+    }
+
+    pub fn reversed(self) -> Self {
+        Self {
+            descending: !self.descending,
+            ..self
+        }
+    }
+
+    // Directories always come first; the order is applied within each group.
+    pub fn compare(
+        &self,
+        (a_name, a_meta): (&str, Option<&Metadata>),
+        (b_name, b_meta): (&str, Option<&Metadata>),
+    ) -> Ordering {
+        let a_is_dir = a_meta.is_some_and(|meta| meta.is_dir());
+        let b_is_dir = b_meta.is_some_and(|meta| meta.is_dir());
+        if a_is_dir != b_is_dir {
+            return if a_is_dir {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            };
+        }
+        let ordering = match self.key {
+            SortKey::Name => Ordering::Equal,
+            SortKey::Extension => natural_cmp(&extension(a_name), &extension(b_name)),
+            SortKey::Size if a_is_dir => Ordering::Equal,
+            SortKey::Size => a_meta
+                .map(|meta| meta.len())
+                .cmp(&b_meta.map(|meta| meta.len())),
+            SortKey::Modified => {
+                let a = a_meta.and_then(|meta| meta.modified().ok());
+                let b = b_meta.and_then(|meta| meta.modified().ok());
+                a.cmp(&b)
+            }
+            SortKey::Created => {
+                let a = a_meta.and_then(|meta| meta.created().ok());
+                let b = b_meta.and_then(|meta| meta.created().ok());
+                a.cmp(&b)
+            }
+        };
+        let ordering = ordering.then_with(|| natural_cmp(a_name, b_name));
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
@@ -0,0 +1,434 @@
+use chrono::Local;
+use crossterm::event::{KeyCode, KeyEvent};
+use std::{
+    ffi::OsString,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Component, Path, PathBuf},
+};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Clear, Row, Table, TableState},
+    Frame,
+};
+
+use crate::action::Action;
+
+pub struct TrashEntry {
+    pub info: PathBuf,
+    pub file: PathBuf,
+    pub original: PathBuf,
+    pub deleted: String,
+}
+
+// Names that aren't valid UTF-8 keep their raw bytes through the encoding.
+#[cfg(unix)]
+fn path_to_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn path_to_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().as_bytes().to_vec()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).to_string())
+}
+
+fn encode_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for b in path_to_bytes(path) {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(b as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
+
+fn decode_path(s: &str) -> PathBuf {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    path_from_bytes(decoded)
+}
+
+#[cfg(unix)]
+fn device(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    fs::symlink_metadata(path).ok().map(|meta| meta.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_path: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(unix)]
+fn uid() -> u32 {
+    unsafe { libc::getuid() }
+}
+
+#[cfg(not(unix))]
+fn uid() -> u32 {
+    0
+}
+
+#[cfg(unix)]
+fn is_admin_trash(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    match fs::symlink_metadata(path) {
+        Ok(meta) => meta.is_dir() && meta.permissions().mode() & 0o1000 != 0,
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_admin_trash(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn create_private_dir(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    fs::DirBuilder::new().mode(0o700).create(path)
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(path: &Path) -> io::Result<()> {
+    fs::create_dir(path)
+}
+
+fn home_trash() -> io::Result<PathBuf> {
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| dirs::home_dir().map(|dir| dir.join(".local").join("share")))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "home directory not found"))?;
+    Ok(data_dir.join("Trash"))
+}
+
+fn prepare(trash: &Path) -> io::Result<()> {
+    fs::create_dir_all(trash.join("files"))?;
+    fs::create_dir_all(trash.join("info"))
+}
+
+fn mount_root(path: &Path) -> PathBuf {
+    let dev = device(path);
+    let mut root = path;
+    while let Some(parent) = root.parent() {
+        if device(parent) != dev {
+            break;
+        }
+        root = parent;
+    }
+    root.into()
+}
+
+// The trash directory to use for `path`, and the top directory that
+// relative paths in its .trashinfo files are resolved against.
+fn trash_for(path: &Path) -> io::Result<(PathBuf, Option<PathBuf>)> {
+    let home = home_trash()?;
+    prepare(&home)?;
+    let parent = path.parent().unwrap_or(path);
+    if device(parent) == device(&home) {
+        return Ok((home, None));
+    }
+    let top = mount_root(parent);
+    let admin = top.join(".Trash");
+    let trash = if is_admin_trash(&admin) {
+        let trash = admin.join(uid().to_string());
+        if !trash.is_dir() {
+            create_private_dir(&trash)?;
+        }
+        trash
+    } else {
+        let trash = top.join(format!(".Trash-{}", uid()));
+        if !trash.is_dir() {
+            create_private_dir(&trash)?;
+        }
+        trash
+    };
+    prepare(&trash)?;
+    Ok((trash, Some(top)))
+}
+
+fn top_dir(trash: &Path) -> Option<PathBuf> {
+    let name = trash.file_name()?.to_string_lossy().to_string();
+    if name.starts_with(".Trash-") {
+        trash.parent().map(PathBuf::from)
+    } else if trash.parent()?.file_name()? == ".Trash" {
+        trash.parent()?.parent().map(PathBuf::from)
+    } else {
+        None
+    }
+}
+
+pub fn trash(path: &Path) -> io::Result<PathBuf> {
+    let (trash, top) = trash_for(path)?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid path"))?;
+    let original = match &top {
+        Some(top) => path.strip_prefix(top).unwrap_or(path),
+        None => path,
+    };
+    let content = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(original),
+        Local::now().format("%Y-%m-%dT%H:%M:%S")
+    );
+
+    for n in 1.. {
+        let mut trash_name = OsString::from(name);
+        if n > 1 {
+            trash_name.push(format!(".{}", n));
+        }
+        let mut info_name = trash_name.clone();
+        info_name.push(".trashinfo");
+        let info = trash.join("info").join(info_name);
+        let file = trash.join("files").join(&trash_name);
+        if fs::symlink_metadata(&file).is_ok() {
+            continue;
+        }
+        let mut writer = match OpenOptions::new().write(true).create_new(true).open(&info) {
+            Ok(writer) => writer,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        };
+        let result = writer
+            .write_all(content.as_bytes())
+            .and_then(|_| fs::rename(path, &file));
+        if result.is_err() {
+            let _ = fs::remove_file(&info);
+        }
+        return result.map(|_| info);
+    }
+    unreachable!()
+}
+
+fn read_info(trash: &Path, info: &Path) -> Option<TrashEntry> {
+    let content = fs::read_to_string(info).ok()?;
+    let mut original = None;
+    let mut deleted = String::new();
+    for line in content.lines() {
+        if let Some(path) = line.strip_prefix("Path=") {
+            original = Some(decode_path(path));
+        } else if let Some(date) = line.strip_prefix("DeletionDate=") {
+            deleted = date.replace('T', " ");
+        }
+    }
+    let original = original?;
+    let original = match top_dir(trash) {
+        Some(top) if original.is_relative() => top.join(original),
+        _ => original,
+    };
+    let file = trash.join("files").join(info.file_stem()?);
+    Some(TrashEntry {
+        info: info.into(),
+        file,
+        original,
+        deleted,
+    })
+}
+
+fn mount_points() -> Vec<PathBuf> {
+    let mounts = fs::read_to_string("/proc/self/mounts").unwrap_or_default();
+    mounts
+        .lines()
+        .filter_map(|line| line.split(' ').nth(1))
+        .map(|point| PathBuf::from(point.replace("\\040", " ")))
+        .collect()
+}
+
+fn trash_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Ok(home) = home_trash() {
+        dirs.push(home);
+    }
+    for point in mount_points() {
+        dirs.push(point.join(".Trash").join(uid().to_string()));
+        dirs.push(point.join(format!(".Trash-{}", uid())));
+    }
+    dirs.sort();
+    dirs.dedup();
+    dirs.into_iter()
+        .filter(|dir| dir.join("info").is_dir())
+        .collect()
+}
+
+pub fn list() -> Vec<TrashEntry> {
+    let mut entries = trash_dirs()
+        .iter()
+        .flat_map(|trash| {
+            fs::read_dir(trash.join("info"))
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| {
+                    entry
+                        .path()
+                        .extension()
+                        .is_some_and(|ext| ext == "trashinfo")
+                })
+                .filter_map(move |entry| read_info(trash, &entry.path()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| b.deleted.cmp(&a.deleted));
+    entries
+}
+
+pub fn restore(info: &Path) -> io::Result<PathBuf> {
+    let trash = info
+        .parent()
+        .and_then(|dir| dir.parent())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid trash entry"))?;
+    let entry = read_info(trash, info)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "broken trash entry"))?;
+    if entry
+        .original
+        .components()
+        .any(|c| c == Component::ParentDir)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "broken trash entry",
+        ));
+    }
+    if fs::symlink_metadata(&entry.original).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", entry.original.display()),
+        ));
+    }
+    if let Some(parent) = entry.original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(&entry.file, &entry.original)?;
+    fs::remove_file(&entry.info)?;
+    Ok(entry.original)
+}
+
+pub struct TrashView {
+    entries: Vec<TrashEntry>,
+    state: TableState,
+}
+
+impl TrashView {
+    pub fn new() -> Self {
+        let mut view = Self {
+            entries: Vec::new(),
+            state: TableState::default(),
+        };
+        view.refresh();
+        view
+    }
+
+    pub fn refresh(&mut self) {
+        self.entries = list();
+        let index = self.state.selected().unwrap_or_default();
+        self.state.select(if self.entries.is_empty() {
+            None
+        } else {
+            Some(index.min(self.entries.len() - 1))
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.entries
+            .iter()
+            .flat_map(|entry| vec![entry.file.clone(), entry.info.clone()])
+            .filter(|path| fs::symlink_metadata(path).is_ok())
+            .collect()
+    }
+
+    pub fn on_event(&mut self, key: &KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                if let Some(index) = self.state.selected() {
+                    self.state
+                        .select(Some((index + 1).min(self.entries.len() - 1)));
+                }
+                None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                if let Some(index) = self.state.selected() {
+                    self.state.select(Some(index.saturating_sub(1)));
+                }
+                None
+            }
+            KeyCode::Char('r') => self
+                .state
+                .selected()
+                .map(|index| Action::RestoreTrash(self.entries[index].info.clone())),
+            KeyCode::Char('E') => Some(Action::EmptyTrash),
+            KeyCode::Esc | KeyCode::Char('q') => Some(Action::CloseTrash),
+            _ => None,
+        }
+    }
+
+    pub fn on_draw<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let list = self
+            .entries
+            .iter()
+            .map(|entry| {
+                Row::new(vec![
+                    entry.deleted.clone(),
+                    entry.original.to_string_lossy().to_string(),
+                ])
+            })
+            .collect::<Vec<_>>();
+        let widths = {
+            let date_width = 19u16;
+            let path_width = area.width.saturating_sub(date_width + 3 /* for borders */);
+            [
+                Constraint::Length(date_width),
+                Constraint::Length(path_width),
+            ]
+        };
+        let title = format!(
+            "Trash ({}) r: restore  E: empty  Esc: close",
+            self.entries.len()
+        );
+        let table = Table::new(list)
+            .widths(&widths)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::UNDERLINED));
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(table, area, &mut self.state);
+    }
+}
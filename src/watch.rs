@@ -0,0 +1,90 @@
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::mpsc::{channel, Receiver},
+    time::{Duration, Instant},
+};
+
+// A directory is reported once its events have stopped for DEBOUNCE, or
+// MAX_DELAY after the first one while they keep coming.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+const MAX_DELAY: Duration = Duration::from_secs(1);
+
+pub struct Watcher {
+    watcher: RecommendedWatcher,
+    rx: Receiver<notify::Result<Event>>,
+    watched: Vec<PathBuf>,
+    pending: HashMap<PathBuf, (Instant, Instant)>,
+}
+
+impl Watcher {
+    pub fn new() -> notify::Result<Self> {
+        let (tx, rx) = channel();
+        let watcher = RecommendedWatcher::new(tx, notify::Config::default())?;
+        Ok(Self {
+            watcher,
+            rx,
+            watched: Vec::new(),
+            pending: HashMap::new(),
+        })
+    }
+
+    // Watches exactly `paths`, not their subdirectories.
+    pub fn watch(&mut self, paths: &[PathBuf]) {
+        for path in self.watched.iter().filter(|path| !paths.contains(path)) {
+            let _ = self.watcher.unwatch(path);
+        }
+        self.pending.retain(|path, _| paths.contains(path));
+        let mut watched = Vec::new();
+        for path in paths {
+            if watched.contains(path) {
+                continue;
+            }
+            if self.watched.contains(path)
+                || self
+                    .watcher
+                    .watch(path, RecursiveMode::NonRecursive)
+                    .is_ok()
+            {
+                watched.push(path.clone());
+            }
+        }
+        self.watched = watched;
+    }
+
+    // The watched directories whose contents changed.
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let now = Instant::now();
+        while let Ok(event) = self.rx.try_recv() {
+            let event = match event {
+                // Reading a directory is an access too, so these would
+                // come back after every refresh.
+                Ok(event) if !matches!(event.kind, EventKind::Access(_)) => event,
+                _ => continue,
+            };
+            for path in event.paths.iter() {
+                let dir = self
+                    .watched
+                    .iter()
+                    .find(|dir| path == *dir || path.parent() == Some(dir.as_path()));
+                if let Some(dir) = dir {
+                    let (_, last) = self.pending.entry(dir.clone()).or_insert((now, now));
+                    *last = now;
+                }
+            }
+        }
+        let due = self
+            .pending
+            .iter()
+            .filter(|(_, (first, last))| {
+                now.duration_since(*last) >= DEBOUNCE || now.duration_since(*first) >= MAX_DELAY
+            })
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        for path in due.iter() {
+            self.pending.remove(path);
+        }
+        due
+    }
+}